@@ -0,0 +1,10 @@
+use clap::ValueEnum;
+
+/// How the backup of a replaced destination file is named.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum BackupMode {
+  /// Append --backup-suffix to the file name
+  Suffix,
+  /// Append the time the run started, such as ".20240131-093000"
+  Timestamp,
+}
@@ -0,0 +1,91 @@
+use std::str::FromStr;
+use regex::Regex;
+use once_cell::sync::Lazy;
+use std::fmt;
+
+use crate::model::size_pretty;
+
+pub static BANDWIDTH_REG: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d+)(KB|MB|GB)/s$").unwrap());
+
+/// The most bytes per second to copy, across all files
+#[derive(Debug, PartialEq, Clone)]
+pub struct BandwidthLimit(u64);
+
+impl BandwidthLimit {
+
+  pub fn new(bytes_per_second: u64) -> Self {
+    Self(bytes_per_second)
+  }
+
+  pub fn bytes_per_second(&self) -> u64 {
+    self.0
+  }
+}
+
+impl FromStr for BandwidthLimit {
+  type Err = String;
+
+  fn from_str(arg: &str) -> Result<Self, Self::Err> {
+    match BANDWIDTH_REG.captures(arg) {
+      Some(matches) => {
+        let amount =
+          matches[1]
+            .parse::<u64>()
+            .map_err(|e| format!("Invalid bandwidth limit supplied: {}. {}", e, BandwidthLimit::FORMAT))?;
+
+        let unit =
+          match &matches[2] {
+            "KB" => 1024,
+            "MB" => 1024 * 1024,
+            _ => 1024 * 1024 * 1024,
+          };
+
+        match amount.checked_mul(unit) {
+          Some(0) | None => Err(format!("Invalid bandwidth limit supplied: '{}'. {}", arg, BandwidthLimit::FORMAT)),
+          Some(bytes_per_second) => Ok(BandwidthLimit(bytes_per_second))
+        }
+      },
+      None => Err(format!("Invalid bandwidth limit format supplied: '{}'. {}", arg, BandwidthLimit::FORMAT))
+    }
+  }
+}
+
+impl BandwidthLimit {
+  const FORMAT: &'static str = "Expected format: <num><unit>/s, where num = <number greater than 0>, unit = <KB|MB|GB>";
+}
+
+impl fmt::Display for BandwidthLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      write!(f, "{}/s", size_pretty(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn succeeds_on_megabytes_per_second() {
+      let limit: Result<BandwidthLimit, String> = FromStr::from_str("20MB/s");
+      assert_eq!(limit, Ok(BandwidthLimit(20 * 1024 * 1024)))
+    }
+
+    #[test]
+    fn succeeds_on_kilobytes_per_second() {
+      let limit: Result<BandwidthLimit, String> = FromStr::from_str("512KB/s");
+      assert_eq!(limit, Ok(BandwidthLimit(512 * 1024)))
+    }
+
+    #[test]
+    fn fails_without_per_second() {
+      let limit = <BandwidthLimit as FromStr>::from_str("20MB").unwrap_err();
+      assert_eq!(limit, "Invalid bandwidth limit format supplied: '20MB'. Expected format: <num><unit>/s, where num = <number greater than 0>, unit = <KB|MB|GB>".to_owned())
+    }
+
+    #[test]
+    fn fails_on_zero() {
+      let limit = <BandwidthLimit as FromStr>::from_str("0MB/s").unwrap_err();
+      assert_eq!(limit, "Invalid bandwidth limit supplied: '0MB/s'. Expected format: <num><unit>/s, where num = <number greater than 0>, unit = <KB|MB|GB>".to_owned())
+    }
+}
@@ -1,9 +1,22 @@
 use std::path::PathBuf;
 use regex::Regex;
 
-use clap::Parser;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
 
+use super::backup::BackupMode;
+use super::bandwidth_limit::BandwidthLimit;
 use super::buffer_size::BufferSize;
+use super::compare_method::CompareMethod;
+use super::output_target::OutputTarget;
+use super::overwrite::OverwritePolicy;
+use super::preserve::PreserveAttribute;
+use super::range_threshold::RangeThreshold;
+use super::report_format::ReportFormat;
+use super::retry_backoff::RetryBackoff;
+use super::symlinks::{ExternalSymlinkPolicy, SymlinkMode};
+use super::sync::SyncMode;
+use super::verification::{ChecksumAlgorithm, VerifyMode};
 
 /// Making progress on your network file copy
 #[derive(Parser, Debug, Clone)]
@@ -43,9 +56,218 @@ pub struct Args {
 
    /// Skip asking verification on copy
    #[arg(long)]
-   pub skip_verify: bool
+   pub skip_verify: bool,
+
+   /// Resume interrupted copies.
+   ///
+   /// Files are copied into a hidden .<name>.coop-partial file next to the destination and renamed into place once verified.
+   /// With --resume, partial files of failed copies are kept, and when a partial file is smaller than the source and its last chunk matches it,
+   /// copying continues from the end of the partial file instead of starting again from zero.
+   #[arg(long)]
+   pub resume: bool,
+
+   /// Skip files that are already up to date at the destination.
+   ///
+   /// Each source file is compared against its destination file using --compare.
+   /// Matching files are skipped and listed as such in coop.log.
+   #[arg(long)]
+   pub incremental: bool,
+
+   /// How to decide whether a destination file is up to date in --incremental mode and with --overwrite if-different.
+   ///
+   /// size-mtime compares sizes and treats a destination modified at or after its source as up to date.
+   /// checksum compares sizes and --checksum checksums, which reads both files in full.
+   #[arg(long, value_enum, default_value_t = CompareMethod::SizeMtime)]
+   pub compare: CompareMethod,
+
+   /// What to do with files that are already at the destination.
+   ///
+   /// Files that aren't overwritten are listed in coop.log with the reason.
+   /// rename copies the source next to the existing file as "name (1).ext", or the next free number.
+   /// Applies to regular files, including each of a set of hard linked files; symlinks at the destination are always replaced.
+   #[arg(long, value_enum, default_value_t = OverwritePolicy::Always)]
+   pub overwrite: OverwritePolicy,
+
+   /// How to verify each copied file.
+   ///
+   /// size compares the source and destination file sizes.
+   /// checksum also hashes the source while it is copied, then re-reads the destination and compares checksums.
+   #[arg(long, value_enum, default_value_t = VerifyMode::Size)]
+   pub verify: VerifyMode,
+
+   /// What to sync to disk before a file is reported as copied.
+   ///
+   /// file fsyncs each file's data before it is moved into place.
+   /// file+dir also fsyncs the directory it was moved into, so the file survives a power cut on the destination.
+   /// none is fastest, but files reported as copied can be lost or empty after a crash.
+   #[arg(long, value_enum, default_value_t = SyncMode::File)]
+   pub sync: SyncMode,
+
+   /// The checksum algorithm used by --verify checksum and --compare checksum.
+   #[arg(long, value_enum, default_value_t = ChecksumAlgorithm::Blake3)]
+   pub checksum: ChecksumAlgorithm,
+
+   /// Source attributes to apply to copied files and directories.
+   ///
+   /// Accepts a comma separated list.
+   /// Timestamps are preserved to nanosecond precision where the filesystem supports it.
+   /// Directory attributes are applied once all their files have been copied.
+   /// Failing to copy xattrs or ACLs is reported as a warning, as the file content was still copied.
+   ///
+   /// Example: --preserve mode,timestamps,xattrs
+   #[arg(long, value_enum, value_delimiter = ',')]
+   pub preserve: Vec<PreserveAttribute>,
+
+   /// Only copy extended attributes in this namespace, when preserving xattrs.
+   ///
+   /// Can be specified multiple times.
+   /// The namespace is the part of the attribute name before the first '.'.
+   ///
+   /// Example: --xattr-namespace user
+   #[arg(long, value_name = "NAMESPACE")]
+   pub xattr_namespace: Vec<String>,
+
+   /// How to copy symlinks found in a source directory.
+   ///
+   /// recreate creates the same link at the destination.
+   /// follow copies what the link points to. Links that loop back on themselves or are broken are skipped.
+   /// skip leaves the link out. Skipped links are listed in coop.log.
+   #[arg(long, value_enum, default_value_t = SymlinkMode::Skip)]
+   pub symlinks: SymlinkMode,
+
+   /// What to do with symlinks that point outside the source directory.
+   #[arg(long, value_enum, default_value_t = ExternalSymlinkPolicy::Skip)]
+   pub external_symlinks: ExternalSymlinkPolicy,
+
+   /// Copy file data within the kernel where possible.
+   ///
+   /// Tries a FICLONE reflink first, which shares the data on copy-on-write filesystems such as btrfs and xfs,
+   /// then copy_file_range, which avoids moving data through user space for local and same-server NFS copies.
+   /// Falls back to buffered reads and writes when neither is supported.
+   /// The method in use is shown in each file's status line.
+   #[arg(long)]
+   pub accelerate: bool,
+
+   /// How many times to retry a file after a transient I/O error, such as EIO, ETIMEDOUT or ESTALE.
+   ///
+   /// Retries reopen the source and partial files and continue from the last offset written to the partial file.
+   /// Each attempt is recorded in coop.log.
+   #[arg(long, default_value = "3")]
+   pub retries: u8,
+
+   /// How long to wait before the first retry. The wait doubles with each further retry, up to a minute. [default: 1s]
+   ///
+   /// Example: --retry-backoff 500ms
+   #[arg(long, value_parser = clap::value_parser!(RetryBackoff))]
+   pub retry_backoff: Option<RetryBackoff>,
+
+   /// Copy each file of at least --range-threshold as this many byte ranges at once (1-16).
+   ///
+   /// Ranges are copied with positional reads and writes into a destination file extended to its full size up front.
+   /// This helps over high-latency links, where a single stream can't keep the link busy.
+   #[arg(long, default_value = "1", value_parser = clap::value_parser!(u8).range(1..=16))]
+   pub ranges: u8,
+
+   /// The smallest file that --ranges applies to.
+   ///
+   /// Example: --range-threshold 1GB
+   #[arg(long, value_name = "SIZE", value_parser = clap::value_parser!(RangeThreshold))]
+   pub range_threshold: Option<RangeThreshold>,
+
+   /// The maximum combined rate of all concurrent copies.
+   ///
+   /// Example: --bwlimit 20MB/s
+   #[arg(long, value_parser = clap::value_parser!(BandwidthLimit))]
+   pub bwlimit: Option<BandwidthLimit>,
+
+   /// A file to change --bwlimit from while copying.
+   ///
+   /// The file is checked every second. It holds a limit in the same format as --bwlimit, or "off" to remove the limit.
+   ///
+   /// Example: echo 5MB/s > coop.bwlimit
+   #[arg(long, value_name = "FILE")]
+   pub bwlimit_file: Option<PathBuf>,
+
+   /// Move files instead of copying them.
+   ///
+   /// Each source file is deleted only once it has been copied and verified.
+   /// Files on the same filesystem as the destination are renamed instead of copied.
+   /// Source directories left empty are removed, apart from the source directory itself.
+   /// Files skipped in --incremental mode are kept.
+   #[arg(long = "move")]
+   pub move_sources: bool,
+
+   /// Remove destination files that have no source file, so the destination mirrors the source.
+   ///
+   /// Files matching --ignore are left alone.
+   /// Files are removed once all copies have completed, and are listed before copying starts and in coop.log.
+   /// Destination directories left empty are removed.
+   #[arg(long)]
+   pub delete: bool,
+
+   /// Move files removed by --delete into this directory, instead of deleting them.
+   #[arg(long, value_name = "DIR", requires = "delete")]
+   pub trash_dir: Option<PathBuf>,
+
+   /// Keep a backup of each destination file before it is replaced.
+   ///
+   /// Backups are made next to the destination file, or under --backup-dir if given.
+   /// Earlier backups with the same name are kept, by giving the new backup a numbered suffix.
+   #[arg(long, value_enum)]
+   pub backup: Option<BackupMode>,
+
+   /// Appended to the names of backups made by --backup suffix.
+   #[arg(long, value_name = "SUFFIX", default_value = "~")]
+   pub backup_suffix: String,
+
+   /// Keep backups of replaced destination files in this directory, at the same relative path.
+   ///
+   /// They keep their names unless --backup is also used.
+   #[arg(long, value_name = "DIR")]
+   pub backup_dir: Option<PathBuf>,
+
+   /// Print what would be done to every file, and why, without copying anything.
+   ///
+   /// The whole plan is printed, including skipped files, links, directories that would be created
+   /// and files --delete would remove. Nothing is written to the destination and no coop.log is created.
+   /// With --compare checksum, files are read to compare them, just as the copy would.
+   #[arg(long)]
+   pub dry_run: bool,
+
+   /// Write every file's progress as newline-delimited JSON to this file, or to stdout with -.
+   ///
+   /// Each line is an event with a timestamp, the file's path relative to the source, and any byte counts or error text.
+   /// Progress is reported at most every 500ms per file. With -, the confirmation screen and summary are written to stderr.
+   #[arg(long, value_name = "PATH")]
+   pub events: Option<OutputTarget>,
+
+   /// Write a report of every file and the run's totals to this file once copying has finished, or to stdout with -.
+   ///
+   /// Each file has its path relative to the source, size, status, skip or failure reason, start and end times,
+   /// duration, throughput and, with --verify checksum, its checksum.
+   #[arg(long, value_name = "PATH")]
+   pub report: Option<OutputTarget>,
+
+   /// The format of --report. Defaults to csv for files ending in .csv, and json otherwise.
+   #[arg(long, value_enum, requires = "report")]
+   pub report_format: Option<ReportFormat>,
+}
+
+impl Args {
+  /// Whether --events or --report is written to stdout, so nothing else should be.
+  pub fn stdout_taken(&self) -> bool {
+    self.events == Some(OutputTarget::Stdout) || self.report == Some(OutputTarget::Stdout)
+  }
 }
 
 pub fn get_cli_args() -> Args {
-  Args::parse()
+  let args = Args::parse();
+
+  if args.events == Some(OutputTarget::Stdout) && args.report == Some(OutputTarget::Stdout) {
+    Args::command()
+      .error(ErrorKind::ArgumentConflict, "--events and --report can't both be written to stdout")
+      .exit()
+  }
+
+  args
 }
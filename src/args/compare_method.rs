@@ -0,0 +1,10 @@
+use clap::ValueEnum;
+
+/// How a source file is compared against an existing destination file.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum CompareMethod {
+  /// The sizes match and the destination was modified at or after the source
+  SizeMtime,
+  /// The sizes and content checksums match
+  Checksum
+}
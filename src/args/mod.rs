@@ -1,4 +1,28 @@
 pub mod cli;
+pub mod backup;
+pub mod bandwidth_limit;
 pub mod buffer_size;
+pub mod compare_method;
+pub mod output_target;
+pub mod overwrite;
+pub mod preserve;
+pub mod range_threshold;
+pub mod report_format;
+pub mod retry_backoff;
+pub mod symlinks;
+pub mod sync;
+pub mod verification;
 
+pub use backup::BackupMode;
+pub use bandwidth_limit::BandwidthLimit;
 pub use buffer_size::BufferSize;
+pub use compare_method::CompareMethod;
+pub use output_target::OutputTarget;
+pub use overwrite::OverwritePolicy;
+pub use preserve::PreserveAttribute;
+pub use range_threshold::RangeThreshold;
+pub use report_format::ReportFormat;
+pub use retry_backoff::RetryBackoff;
+pub use symlinks::{ExternalSymlinkPolicy, SymlinkMode};
+pub use sync::SyncMode;
+pub use verification::{ChecksumAlgorithm, VerifyMode};
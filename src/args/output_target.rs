@@ -0,0 +1,66 @@
+use std::str::FromStr;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::fmt;
+
+/// Where machine-readable output is written: a file, or stdout when given as "-".
+#[derive(Debug, PartialEq, Clone)]
+pub enum OutputTarget {
+  Stdout,
+  File(PathBuf),
+}
+
+impl OutputTarget {
+
+  /// Creates the file, replacing any that's already there
+  pub fn open(&self) -> std::io::Result<Box<dyn Write + Send>> {
+    match self {
+      OutputTarget::Stdout => Ok(Box::new(std::io::stdout())),
+      OutputTarget::File(path) => Ok(Box::new(BufWriter::new(File::create(path)?)))
+    }
+  }
+}
+
+impl FromStr for OutputTarget {
+  type Err = String;
+
+  fn from_str(arg: &str) -> Result<Self, Self::Err> {
+    match arg {
+      "" => Err("Invalid output supplied: ''. Expected a file path, or - for stdout".to_owned()),
+      "-" => Ok(OutputTarget::Stdout),
+      path => Ok(OutputTarget::File(PathBuf::from(path)))
+    }
+  }
+}
+
+impl fmt::Display for OutputTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      match self {
+        OutputTarget::Stdout => write!(f, "-"),
+        OutputTarget::File(path) => write!(f, "{}", path.to_string_lossy()),
+      }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_a_dash_as_stdout() {
+      assert_eq!(OutputTarget::from_str("-"), Ok(OutputTarget::Stdout))
+    }
+
+    #[test]
+    fn parses_anything_else_as_a_file() {
+      assert_eq!(OutputTarget::from_str("events.ndjson"), Ok(OutputTarget::File(PathBuf::from("events.ndjson"))));
+      assert_eq!(OutputTarget::from_str("events.ndjson").map(|t| t.to_string()), Ok("events.ndjson".to_owned()))
+    }
+
+    #[test]
+    fn does_not_parse_an_empty_path() {
+      assert!(OutputTarget::from_str("").is_err())
+    }
+}
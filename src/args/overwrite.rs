@@ -0,0 +1,16 @@
+use clap::ValueEnum;
+
+/// What to do with a file that is already at the destination.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum OverwritePolicy {
+  /// Keep the existing file and skip the source file
+  Never,
+  /// Replace the existing file
+  Always,
+  /// Replace the existing file if the source was modified after it
+  IfNewer,
+  /// Replace the existing file if it differs from the source, using --compare
+  IfDifferent,
+  /// Keep the existing file and copy the source next to it as "name (1).ext"
+  Rename,
+}
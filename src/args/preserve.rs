@@ -0,0 +1,14 @@
+use clap::ValueEnum;
+
+/// Attributes of a source file that can be applied to its copy.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum PreserveAttribute {
+  /// Permission bits
+  Mode,
+  /// Access and modification times
+  Timestamps,
+  /// Extended attributes, optionally filtered with --xattr-namespace
+  Xattrs,
+  /// POSIX ACLs
+  Acls
+}
@@ -0,0 +1,89 @@
+use std::str::FromStr;
+use regex::Regex;
+use once_cell::sync::Lazy;
+use std::fmt;
+
+use crate::model::size_pretty;
+
+pub static RANGE_THRESHOLD_REG: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d+)(KB|MB|GB)$").unwrap());
+
+/// The smallest file that is copied as several byte ranges at once
+#[derive(Debug, PartialEq, Clone)]
+pub struct RangeThreshold(u64);
+
+impl RangeThreshold {
+
+  pub const DEFAULT_RANGE_THRESHOLD: RangeThreshold = RangeThreshold(256 * 1024 * 1024);
+
+  pub fn bytes(&self) -> u64 {
+    self.0
+  }
+}
+
+impl FromStr for RangeThreshold {
+  type Err = String;
+
+  fn from_str(arg: &str) -> Result<Self, Self::Err> {
+    match RANGE_THRESHOLD_REG.captures(arg) {
+      Some(matches) => {
+        let amount =
+          matches[1]
+            .parse::<u64>()
+            .map_err(|e| format!("Invalid range threshold supplied: {}. {}", e, RangeThreshold::FORMAT))?;
+
+        let unit =
+          match &matches[2] {
+            "KB" => 1024,
+            "MB" => 1024 * 1024,
+            _ => 1024 * 1024 * 1024,
+          };
+
+        match amount.checked_mul(unit) {
+          Some(0) | None => Err(format!("Invalid range threshold supplied: '{}'. {}", arg, RangeThreshold::FORMAT)),
+          Some(bytes) => Ok(RangeThreshold(bytes))
+        }
+      },
+      None => Err(format!("Invalid range threshold format supplied: '{}'. {}", arg, RangeThreshold::FORMAT))
+    }
+  }
+}
+
+impl RangeThreshold {
+  const FORMAT: &'static str = "Expected format: <num><unit>, where num = <number greater than 0>, unit = <KB|MB|GB>";
+}
+
+impl fmt::Display for RangeThreshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      write!(f, "{}", size_pretty(self.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn succeeds_on_gigabytes() {
+      let threshold: Result<RangeThreshold, String> = FromStr::from_str("2GB");
+      assert_eq!(threshold, Ok(RangeThreshold(2 * 1024 * 1024 * 1024)))
+    }
+
+    #[test]
+    fn succeeds_on_kilobytes() {
+      let threshold: Result<RangeThreshold, String> = FromStr::from_str("64KB");
+      assert_eq!(threshold, Ok(RangeThreshold(64 * 1024)))
+    }
+
+    #[test]
+    fn fails_on_zero() {
+      let threshold = <RangeThreshold as FromStr>::from_str("0MB").unwrap_err();
+      assert_eq!(threshold, "Invalid range threshold supplied: '0MB'. Expected format: <num><unit>, where num = <number greater than 0>, unit = <KB|MB|GB>".to_owned())
+    }
+
+    #[test]
+    fn fails_on_unknown_unit() {
+      let threshold = <RangeThreshold as FromStr>::from_str("1TB").unwrap_err();
+      assert_eq!(threshold, "Invalid range threshold format supplied: '1TB'. Expected format: <num><unit>, where num = <number greater than 0>, unit = <KB|MB|GB>".to_owned())
+    }
+}
@@ -0,0 +1,42 @@
+use clap::ValueEnum;
+
+use super::OutputTarget;
+
+/// The format --report is written in.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+  /// A single object with run totals and every file
+  Json,
+  /// One row per file, followed by a row of run totals
+  Csv,
+}
+
+impl ReportFormat {
+
+  /// CSV for files ending in .csv, and JSON for everything else, including stdout
+  pub fn for_target(target: &OutputTarget) -> Self {
+    match target {
+      OutputTarget::File(path) if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv")) => ReportFormat::Csv,
+      _ => ReportFormat::Json
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    #[test]
+    fn uses_csv_for_csv_files() {
+      assert_eq!(ReportFormat::for_target(&OutputTarget::File(PathBuf::from("report.CSV"))), ReportFormat::Csv)
+    }
+
+    #[test]
+    fn uses_json_for_everything_else() {
+      assert_eq!(ReportFormat::for_target(&OutputTarget::File(PathBuf::from("report.json"))), ReportFormat::Json);
+      assert_eq!(ReportFormat::for_target(&OutputTarget::File(PathBuf::from("report"))), ReportFormat::Json);
+      assert_eq!(ReportFormat::for_target(&OutputTarget::Stdout), ReportFormat::Json)
+    }
+}
@@ -0,0 +1,90 @@
+use std::str::FromStr;
+use std::time::Duration;
+use regex::Regex;
+use once_cell::sync::Lazy;
+use std::fmt;
+
+pub static BACKOFF_REG: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d+)(ms|s)$").unwrap());
+
+/// How long to wait before the first retry. Each further retry waits twice as long as the one before.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RetryBackoff(Duration);
+
+impl RetryBackoff {
+
+  // 1s
+  pub const DEFAULT_RETRY_BACKOFF: RetryBackoff = RetryBackoff(Duration::from_secs(1));
+
+  pub fn duration(&self) -> Duration {
+    self.0
+  }
+}
+
+impl FromStr for RetryBackoff {
+  type Err = String;
+
+  fn from_str(arg: &str) -> Result<Self, Self::Err> {
+    match BACKOFF_REG.captures(arg) {
+      Some(matches) => {
+        let amount =
+          matches[1]
+            .parse::<u64>()
+            .map_err(|e| format!("Invalid retry backoff supplied: {}. {}", e, RetryBackoff::FORMAT))?;
+
+        let duration =
+          if &matches[2] == "s" {
+            Duration::from_secs(amount)
+          } else {
+            Duration::from_millis(amount)
+          };
+
+        Ok(RetryBackoff(duration))
+      },
+      None => Err(format!("Invalid retry backoff format supplied: '{}'. {}", arg, RetryBackoff::FORMAT))
+    }
+  }
+}
+
+impl RetryBackoff {
+  const FORMAT: &'static str = "Expected format: <num><unit>, where num = <number>, unit = <ms|s>";
+}
+
+impl fmt::Display for RetryBackoff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      if self.0.subsec_millis() == 0 {
+        write!(f, "{}s", self.0.as_secs())
+      } else {
+        write!(f, "{}ms", self.0.as_millis())
+      }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn succeeds_on_seconds() {
+      let backoff: Result<RetryBackoff, String> = FromStr::from_str("2s");
+      assert_eq!(backoff, Ok(RetryBackoff(Duration::from_secs(2))))
+    }
+
+    #[test]
+    fn succeeds_on_milliseconds() {
+      let backoff: Result<RetryBackoff, String> = FromStr::from_str("250ms");
+      assert_eq!(backoff, Ok(RetryBackoff(Duration::from_millis(250))))
+    }
+
+    #[test]
+    fn fails_with_invalid_unit() {
+      let backoff = <RetryBackoff as FromStr>::from_str("1m").unwrap_err();
+      assert_eq!(backoff, "Invalid retry backoff format supplied: '1m'. Expected format: <num><unit>, where num = <number>, unit = <ms|s>".to_owned())
+    }
+
+    #[test]
+    fn displays_in_the_largest_whole_unit() {
+      assert_eq!(RetryBackoff(Duration::from_secs(3)).to_string(), "3s");
+      assert_eq!(RetryBackoff(Duration::from_millis(1500)).to_string(), "1500ms")
+    }
+}
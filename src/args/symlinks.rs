@@ -0,0 +1,21 @@
+use clap::ValueEnum;
+
+/// What to do with symlinks found in the source.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum SymlinkMode {
+  /// Create the same link at the destination
+  Recreate,
+  /// Copy what the link points to
+  Follow,
+  /// Don't copy the link
+  Skip
+}
+
+/// What to do with symlinks that point outside the source directory.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum ExternalSymlinkPolicy {
+  /// Handle them like any other symlink
+  Allow,
+  /// Don't copy them
+  Skip
+}
@@ -0,0 +1,13 @@
+use clap::ValueEnum;
+
+/// What is synced to disk before a copied file is reported as complete.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum SyncMode {
+  /// Leave it to the operating system to write data out
+  None,
+  /// Sync each copied file's data before it is moved into place
+  File,
+  /// Also sync the directory each file is moved into, so the new name survives a power cut
+  #[value(name = "file+dir")]
+  FileAndDir,
+}
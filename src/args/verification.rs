@@ -0,0 +1,17 @@
+use clap::ValueEnum;
+
+/// How a copied file is verified against its source.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum VerifyMode {
+  /// Compare file sizes
+  Size,
+  /// Compare file sizes and content checksums
+  Checksum
+}
+
+/// Hash function used for content checksums.
+#[derive(Debug, PartialEq, Clone, Copy, ValueEnum)]
+pub enum ChecksumAlgorithm {
+  Blake3,
+  Sha256
+}
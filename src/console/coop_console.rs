@@ -1,7 +1,8 @@
 use dialoguer::{theme::ColorfulTheme, FuzzySelect};
-use console::style;
-use crate::{args::BufferSize, copy::SourceFile};
-use crate::model::size_pretty;
+use console::{style, Term};
+use crate::args::BufferSize;
+use crate::model::{size_pretty, RunSummary};
+use crate::plan::{CopyAction, CopyPlan, ExistingDestination, PlanEntry};
 
 pub struct CoopConsole;
 
@@ -14,36 +15,107 @@ pub enum UserResult {
 
 impl CoopConsole {
   pub fn show_copy_state(
-    files_to_copy: &[SourceFile],
+    copy_plan: &CopyPlan,
     concurrency: u8,
     buffer_size: &BufferSize,
     destination_dir: &str,
-    total_file_size: u64
+    move_sources: bool,
+    term: &Term,
   ) -> UserResult {
-    let files: Vec<(String, u64)> =
-      files_to_copy
+    let files: Vec<&PlanEntry> =
+      copy_plan
+        .entries()
         .iter()
-        .map(|sf| (sf.relative_path().clone(), sf.size()))
         .take(50)
         .collect();
 
-    println!("{}:", style("Source files").green());
-    for (index, (file, size)) in files.iter().enumerate() {
-      println!("  {:06} - {} ({})", index + 1, style(file).cyan(), style(size_pretty(*size)).yellow())
+    let _ = term.write_line(&format!("{}:", style("Source files").green()));
+    for (index, entry) in files.iter().enumerate() {
+      let file = entry.source_file().relative_path();
+      let size = entry.source_file().size();
+      let line =
+        match entry.action() {
+          CopyAction::Copy if entry.existing_destination() == ExistingDestination::Renamed =>
+            format!("  {:06} - {} ({}) {}", index + 1, style(&file).cyan(), style(size_pretty(size)).yellow(), style(format!("as {}", Self::file_name(entry))).magenta()),
+          CopyAction::Copy =>
+            format!("  {:06} - {} ({})", index + 1, style(file).cyan(), style(size_pretty(size)).yellow()),
+          CopyAction::CreateDirectory =>
+            format!("  {:06} - {}", index + 1, style(format!("{file}/")).blue()),
+          CopyAction::Symlink(target) =>
+            format!("  {:06} - {} -> {}", index + 1, style(file).cyan(), style(target.to_string_lossy()).magenta()),
+          CopyAction::HardLink(linked) =>
+            format!("  {:06} - {} => {}", index + 1, style(file).cyan(), style(linked.to_string_lossy()).magenta()),
+          CopyAction::Skip(reason) =>
+            format!("  {:06} - {} ({}) {}", index + 1, style(file).dim(), style(size_pretty(size)).yellow(), style(format!("skip: {reason}")).dim()),
+        };
+
+      let _ = term.write_line(&line);
     }
 
-    let num_files = files_to_copy.len();
+    let num_entries = copy_plan.len();
     let displayed_num_files = files.len();
 
-    if num_files > displayed_num_files {
-      println!(" + ({})", num_files - displayed_num_files)
+    if num_entries > displayed_num_files {
+      let _ = term.write_line(&format!(" + ({})", num_entries - displayed_num_files));
+    }
+
+    let (num_skipped, skipped_size) =
+      copy_plan
+        .skipped()
+        .fold((0, 0), |(count, size), e| (count + 1, size + e.source_file().size()));
+
+    let _ = term.write_line(&format!("{}: {}", style("Concurrency").green(), concurrency));
+    let _ = term.write_line(&format!("{}: {}", style("Buffer size").green(), buffer_size));
+    let _ = term.write_line(&format!("{}: {}", style("Destination").green(), destination_dir));
+    let _ = term.write_line(&format!("{}: {}", style("Files").green(), copy_plan.num_files()));
+    let directories = copy_plan.directories();
+    if !directories.is_empty() {
+      let num_new_directories = directories.iter().filter(|(_, destination)| !destination.exists()).count();
+      let _ = term.write_line(&format!("{}: {} ({} new)", style("Directories").green(), directories.len(), num_new_directories));
     }
+    let num_hard_links = copy_plan.hard_links().count();
+    if num_hard_links > 0 {
+      let _ = term.write_line(&format!("{}: {}", style("Hard links").green(), num_hard_links));
+    }
+    if num_skipped > 0 {
+      let _ = term.write_line(&format!("{}: {} ({})", style("Skipped").green(), num_skipped, size_pretty(skipped_size)));
+    }
+    let num_overwritten = copy_plan.existing_destinations(ExistingDestination::Overwritten).count();
+    let num_renamed = copy_plan.existing_destinations(ExistingDestination::Renamed).count();
+    let num_kept = copy_plan.existing_destinations(ExistingDestination::Kept).count();
+    if num_overwritten + num_renamed + num_kept > 0 {
+      let _ = term.write_line(&format!(
+        "{}: {} overwritten, {} copied under a new name, {} not overwritten",
+        style("Existing files").green(),
+        style(num_overwritten).red(),
+        num_renamed,
+        num_kept
+      ));
+    }
+    let _ = term.write_line(&format!("{}: {}", style("Total size").green(), size_pretty(copy_plan.total_bytes())));
+    let extraneous_files = copy_plan.extraneous_files();
+    if !extraneous_files.is_empty() {
+      let removal =
+        match copy_plan.trash_dir() {
+          Some(trash_dir) => format!("Move to {}", trash_dir.to_string_lossy()),
+          None => "Delete".to_owned()
+        };
+
+      let _ = term.write_line(&format!("{}:", style(&removal).red()));
+      for file in extraneous_files.iter().take(50) {
+        let _ = term.write_line(&format!("  {} ({})", style(file.relative_path()).red(), style(size_pretty(file.size())).yellow()));
+      }
 
-    println!("{}: {}", style("Concurrency").green(), concurrency);
-    println!("{}: {}", style("Buffer size").green(), buffer_size);
-    println!("{}: {}", style("Destination").green(), destination_dir);
-    println!("{}: {}", style("Files").green(), num_files);
-    println!("{}: {}", style("Total size").green(), size_pretty(total_file_size));
+      if extraneous_files.len() > 50 {
+        let _ = term.write_line(&format!(" + ({})", extraneous_files.len() - 50));
+      }
+
+      let removed_size: u64 = extraneous_files.iter().map(|f| f.size()).sum();
+      let _ = term.write_line(&format!("{}: {} ({})", style("Extraneous files").green(), extraneous_files.len(), size_pretty(removed_size)));
+    }
+    if move_sources {
+      let _ = term.write_line(&format!("{}: {}", style("Move").green(), style("source files are deleted once copied and verified").red()));
+    }
 
     let options = ["no", "yes"];
 
@@ -67,7 +139,116 @@ impl CoopConsole {
             })
         });
 
-    selection.map_or_else(|e| e, |v| v)
+    selection.unwrap_or_else(|e| e)
+  }
+
+  /// Prints every decision in the plan, one per line, for --dry-run.
+  ///
+  /// Nothing is written to the destination. Destination files are only read when --compare checksum compares them,
+  /// so the plan makes the same decisions as the copy would.
+  pub fn show_plan(copy_plan: &CopyPlan, move_sources: bool, term: &Term) {
+    let copy = if move_sources { "move" } else { "copy" };
+    let mut copied = (0, 0);
+    let mut skipped = 0;
+    let mut links = 0;
+
+    let mut new_directories = 0;
+
+    for entry in copy_plan.entries() {
+      let file = entry.source_file().relative_path();
+      let size = entry.source_file().size();
+
+      match entry.action() {
+        CopyAction::Copy => {
+          let reason =
+            match entry.existing_destination() {
+              ExistingDestination::Overwritten => "replaces existing file".to_owned(),
+              ExistingDestination::Renamed => format!("destination exists, copied as {}", Self::file_name(entry)),
+              ExistingDestination::Missing | ExistingDestination::Kept => "new file".to_owned(),
+            };
+          let _ = term.write_line(&format!("{:<10} {} ({}): {}", copy, file, size_pretty(size), reason));
+          copied = (copied.0 + 1, copied.1 + size);
+        },
+        // Parents are listed before their subdirectories, in the order they are created
+        CopyAction::CreateDirectory => {
+          if !entry.destination().exists() {
+            let _ = term.write_line(&format!("{:<10} {}", "mkdir", entry.destination().to_string_lossy()));
+            new_directories += 1;
+          }
+        },
+        CopyAction::Symlink(target) => {
+          let _ = term.write_line(&format!("{:<10} {} -> {}", "symlink", file, target.to_string_lossy()));
+          links += 1;
+        },
+        CopyAction::HardLink(linked) => {
+          let _ = term.write_line(&format!("{:<10} {} => {}", "hard link", file, linked.to_string_lossy()));
+          links += 1;
+        },
+        CopyAction::Skip(reason) => {
+          let _ = term.write_line(&format!("{:<10} {} ({}): {}", "skip", file, size_pretty(size), reason));
+          skipped += 1;
+        },
+      }
+    }
+
+    let extraneous_files = copy_plan.extraneous_files();
+    for file in extraneous_files {
+      let line =
+        match copy_plan.trash_dir() {
+          Some(trash_dir) => format!("{:<10} {} ({}) -> {}", "trash", file.relative_path(), size_pretty(file.size()), trash_dir.to_string_lossy()),
+          None => format!("{:<10} {} ({})", "delete", file.relative_path(), size_pretty(file.size())),
+        };
+
+      let _ = term.write_line(&line);
+    }
+
+    let _ = term.write_line(&format!(
+      "{}: {} to {copy} ({}), {} skipped, {} links, {} directories to create, {} to remove. Nothing was written.",
+      style("Dry run").green(),
+      copied.0,
+      size_pretty(copied.1),
+      skipped,
+      links,
+      new_directories,
+      extraneous_files.len()
+    ));
+  }
+
+  /// Prints how many files were copied, skipped and failed once a run has finished, with a table of the failures.
+  pub fn show_summary(summary: &RunSummary, term: &Term) {
+    let failures = summary.failures();
+
+    let _ = term.write_line(&format!(
+      "{}: {} copied, {} skipped, {} failed",
+      style("Summary").green(),
+      summary.succeeded(),
+      summary.skipped(),
+      failures.len()
+    ));
+
+    if !failures.is_empty() {
+      // Very long names push their reasons out of line, rather than widening every row
+      let width =
+        failures
+          .iter()
+          .map(|f| f.name().chars().count())
+          .max()
+          .unwrap_or_default()
+          .clamp("File".len(), 60);
+
+      let _ = term.write_line(&format!("  {:<width$}  {}", style("File").bold(), style("Reason").bold()));
+      for failure in failures {
+        let _ = term.write_line(&format!("  {}  {}", style(format!("{:<width$}", failure.name())).red(), failure.reason()));
+      }
+    }
+  }
+
+  fn file_name(entry: &PlanEntry) -> String {
+    entry
+      .destination()
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .unwrap_or_default()
   }
 
 }
@@ -0,0 +1,135 @@
+use std::io;
+use tokio::fs::File;
+
+/// Clones the whole source file into the empty destination file with a FICLONE reflink.
+///
+/// Both files then share the same extents on copy-on-write filesystems such as btrfs and xfs,
+/// so no data is copied at all.
+pub async fn reflink(source: &File, destination: &File) -> io::Result<()> {
+  imp::reflink(source, destination).await
+}
+
+/// Copies up to `length` bytes at `offset` from the source to the same offset in the destination,
+/// within the kernel. Returns the number of bytes copied, which is zero at the end of the source.
+///
+/// Neither file's position is changed.
+pub async fn copy_range(source: &File, destination: &File, offset: u64, length: u64) -> io::Result<u64> {
+  imp::copy_range(source, destination, offset, length).await
+}
+
+/// Whether an accelerated copy failed because the platform or filesystem doesn't support it,
+/// in which case the data can still be copied with reads and writes.
+pub fn is_unsupported(error: &io::Error) -> bool {
+  imp::is_unsupported(error)
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+  use std::io;
+  use std::os::fd::AsRawFd;
+  use tokio::fs::File;
+
+  // _IOW(0x94, 9, int) from linux/fs.h
+  const FICLONE: libc::c_ulong = 0x40049409;
+
+  pub async fn reflink(source: &File, destination: &File) -> io::Result<()> {
+    let source_fd = source.as_raw_fd();
+    let destination_fd = destination.as_raw_fd();
+
+    // Both files outlive the blocking task, as it's awaited here
+    tokio::task::spawn_blocking(move || {
+      // SAFETY: Both file descriptors stay open until the call returns
+      let result = unsafe { libc::ioctl(destination_fd, FICLONE as _, source_fd) };
+
+      if result < 0 {
+        Err(io::Error::last_os_error())
+      } else {
+        Ok(())
+      }
+    })
+    .await
+    .unwrap_or_else(|e| Err(io::Error::other(e)))
+  }
+
+  pub async fn copy_range(source: &File, destination: &File, offset: u64, length: u64) -> io::Result<u64> {
+    let source_fd = source.as_raw_fd();
+    let destination_fd = destination.as_raw_fd();
+
+    // Both files outlive the blocking task, as it's awaited here
+    tokio::task::spawn_blocking(move || {
+      let mut source_offset = offset as libc::loff_t;
+      let mut destination_offset = offset as libc::loff_t;
+
+      // SAFETY: The offsets are valid for the call and the file descriptors stay open until it returns
+      let result =
+        unsafe {
+          libc::copy_file_range(source_fd, &mut source_offset, destination_fd, &mut destination_offset, length as usize, 0)
+        };
+
+      if result < 0 {
+        Err(io::Error::last_os_error())
+      } else {
+        Ok(result as u64)
+      }
+    })
+    .await
+    .unwrap_or_else(|e| Err(io::Error::other(e)))
+  }
+
+  pub fn is_unsupported(error: &io::Error) -> bool {
+    matches!(
+      error.raw_os_error(),
+      Some(libc::EXDEV | libc::ENOSYS | libc::EOPNOTSUPP | libc::ENOTTY | libc::EINVAL | libc::EBADF | libc::EPERM)
+    )
+  }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+  use std::io;
+  use tokio::fs::File;
+
+  pub async fn reflink(_source: &File, _destination: &File) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+  }
+
+  pub async fn copy_range(_source: &File, _destination: &File, _offset: u64, _length: u64) -> io::Result<u64> {
+    Err(io::ErrorKind::Unsupported.into())
+  }
+
+  pub fn is_unsupported(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::Unsupported
+  }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn copies_a_range_at_the_same_offset() {
+      let dir = tempfile::tempdir().unwrap();
+      std::fs::write(dir.path().join("source"), b"0123456789").unwrap();
+
+      let source = File::open(dir.path().join("source")).await.unwrap();
+      let destination = File::create(dir.path().join("destination")).await.unwrap();
+
+      let copied = copy_range(&source, &destination, 2, 5).await.unwrap();
+      drop(destination);
+
+      assert_eq!(copied, 5);
+      assert_eq!(std::fs::read(dir.path().join("destination")).unwrap(), b"\0\x0023456")
+    }
+
+    #[tokio::test]
+    async fn copies_nothing_at_the_end_of_the_source() {
+      let dir = tempfile::tempdir().unwrap();
+      std::fs::write(dir.path().join("source"), b"0123456789").unwrap();
+
+      let source = File::open(dir.path().join("source")).await.unwrap();
+      let destination = File::create(dir.path().join("destination")).await.unwrap();
+
+      assert_eq!(copy_range(&source, &destination, 10, 5).await.unwrap(), 0)
+    }
+}
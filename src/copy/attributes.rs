@@ -0,0 +1,100 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use filetime::FileTime;
+
+use crate::args::PreserveAttribute;
+
+/// POSIX ACLs are stored as extended attributes with these names
+const ACL_ATTRIBUTES: [&str; 2] = ["system.posix_acl_access", "system.posix_acl_default"];
+
+/// Copies the extended attributes and ACLs of `source` to `destination`, as requested.
+///
+/// Every attribute is attempted, and the ones that could not be copied are listed in the returned error.
+pub fn copy_extended_attributes<S: AsRef<Path>, D: AsRef<Path>>(source: S, destination: D, attributes: &[PreserveAttribute], namespaces: &[String]) -> io::Result<()> {
+  let copy_xattrs = attributes.contains(&PreserveAttribute::Xattrs);
+  let copy_acls = attributes.contains(&PreserveAttribute::Acls);
+
+  if !copy_xattrs && !copy_acls {
+    return Ok(())
+  }
+
+  let mut failures = vec![];
+
+  for name in xattr::list(source.as_ref())? {
+    let attribute_name = name.to_string_lossy();
+
+    let wanted =
+      if ACL_ATTRIBUTES.contains(&attribute_name.as_ref()) {
+        copy_acls
+      } else {
+        copy_xattrs && in_namespaces(&attribute_name, namespaces)
+      };
+
+    if wanted {
+      let result =
+        xattr::get(source.as_ref(), &name)
+          .and_then(|value| xattr::set(destination.as_ref(), &name, &value.unwrap_or_default()));
+
+      if let Err(e) = result {
+        failures.push(format!("{attribute_name}: {e}"))
+      }
+    }
+  }
+
+  if failures.is_empty() {
+    Ok(())
+  } else {
+    Err(io::Error::other(failures.join(", ")))
+  }
+}
+
+fn in_namespaces(attribute_name: &str, namespaces: &[String]) -> bool {
+  namespaces.is_empty() ||
+    namespaces
+      .iter()
+      .any(|ns| attribute_name.split('.').next() == Some(ns.as_str()))
+}
+
+/// Applies the requested mode and timestamps of `source` to `destination`. Works for files and directories.
+pub fn preserve_attributes<S: AsRef<Path>, D: AsRef<Path>>(source: S, destination: D, attributes: &[PreserveAttribute]) -> io::Result<()> {
+  if attributes.is_empty() {
+    return Ok(())
+  }
+
+  let source_meta = fs::metadata(source)?;
+
+  if attributes.contains(&PreserveAttribute::Mode) {
+    fs::set_permissions(destination.as_ref(), source_meta.permissions())?;
+  }
+
+  if attributes.contains(&PreserveAttribute::Timestamps) {
+    let accessed = FileTime::from_last_access_time(&source_meta);
+    let modified = FileTime::from_last_modification_time(&source_meta);
+    filetime::set_file_times(destination, accessed, modified)?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes_all_attributes_without_namespaces() {
+      assert!(in_namespaces("user.comment", &[]))
+    }
+
+    #[test]
+    fn includes_attributes_in_namespace() {
+      assert!(in_namespaces("user.comment", &["user".to_owned()]))
+    }
+
+    #[test]
+    fn excludes_attributes_outside_namespace() {
+      assert!(!in_namespaces("security.selinux", &["user".to_owned()]));
+      assert!(!in_namespaces("username.comment", &["user".to_owned()]))
+    }
+}
@@ -0,0 +1,213 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDateTime};
+use filetime::FileTime;
+
+use crate::args::BackupMode;
+use super::file_copy::symlink;
+use super::mirror::unused_path;
+
+/// How timestamped backups are named, after a "."
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Where the previous version of a destination file goes before it is replaced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupPolicy {
+  mode: Option<BackupMode>,
+  /// Appended to the file name of each backup
+  suffix: String,
+  /// Backups are made under this directory instead of next to the destination file
+  backup_dir: Option<PathBuf>,
+  destination_dir: PathBuf,
+}
+
+impl BackupPolicy {
+
+  /// Backups are only made with a backup mode or a backup directory.
+  ///
+  /// Timestamped backups all use the time the run started, so a run's backups can be found together.
+  pub fn new(mode: Option<BackupMode>, suffix: &str, backup_dir: Option<PathBuf>, destination_dir: PathBuf, started: DateTime<Local>) -> Option<Self> {
+    let suffix =
+      match mode {
+        Some(BackupMode::Suffix) => suffix.to_owned(),
+        Some(BackupMode::Timestamp) => format!(".{}", started.format(TIMESTAMP_FORMAT)),
+        None if backup_dir.is_some() => String::new(),
+        None => return None
+      };
+
+    Some(
+      Self {
+        mode,
+        suffix,
+        backup_dir,
+        destination_dir,
+      }
+    )
+  }
+
+  /// Where backups are made instead of next to their destination files, if anywhere
+  pub fn backup_dir(&self) -> Option<&Path> {
+    self.backup_dir.as_deref()
+  }
+
+  /// Whether `file` is named like a backup made next to its destination file, by this run or an earlier one,
+  /// so mirror mode can keep it.
+  pub fn is_backup(&self, file: &Path) -> bool {
+    if self.backup_dir.is_some() {
+      return false
+    }
+
+    let Some(name) = file.file_name().map(|name| name.to_string_lossy()) else {
+      return false
+    };
+
+    // Backups made when an earlier one was already there are numbered
+    let name = without_backup_number(&name);
+
+    match self.mode {
+      Some(BackupMode::Suffix) => !self.suffix.is_empty() && name.len() > self.suffix.len() && name.ends_with(&self.suffix),
+      Some(BackupMode::Timestamp) =>
+        name
+          .rsplit_once('.')
+          .is_some_and(|(stem, timestamp)| !stem.is_empty() && NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).is_ok()),
+      None => false
+    }
+  }
+
+  /// Where the backup of a destination file would be made, before avoiding earlier backups
+  pub fn backup_path(&self, destination_file: &Path) -> PathBuf {
+    let backup_file =
+      match &self.backup_dir {
+        Some(backup_dir) => {
+          let relative_path =
+            destination_file
+              .strip_prefix(&self.destination_dir)
+              .unwrap_or_else(|_| Path::new(destination_file.file_name().unwrap_or_default()));
+
+          backup_dir.join(relative_path)
+        },
+        None => destination_file.to_path_buf()
+      };
+
+    PathBuf::from(format!("{}{}", backup_file.to_string_lossy(), self.suffix))
+  }
+
+  /// Keeps the existing destination file at its backup path, returning where it was kept.
+  ///
+  /// The destination file is left where it is, so it's never missing while it's being replaced.
+  /// The backup is a hard link to it, or a copy when that isn't possible, such as across filesystems.
+  /// Nothing is done if there is no destination file. Directories are never backed up.
+  pub async fn back_up(&self, destination_file: &Path) -> io::Result<Option<PathBuf>> {
+    let metadata =
+      match tokio::fs::symlink_metadata(destination_file).await {
+        Ok(metadata) if !metadata.is_dir() => metadata,
+        _ => return Ok(None)
+      };
+
+    let backup_file = unused_path(&self.backup_path(destination_file)).await;
+    if let Some(parent) = backup_file.parent() {
+      tokio::fs::create_dir_all(parent).await?;
+    }
+
+    if tokio::fs::hard_link(destination_file, &backup_file).await.is_err() {
+      if metadata.is_symlink() {
+        symlink(&tokio::fs::read_link(destination_file).await?, &backup_file).await?
+      } else {
+        tokio::fs::copy(destination_file, &backup_file).await?;
+        filetime::set_file_mtime(&backup_file, FileTime::from_last_modification_time(&metadata))?
+      }
+    }
+
+    Ok(Some(backup_file))
+  }
+}
+
+/// The name without the ".~N~" that unused_path adds
+fn without_backup_number(name: &str) -> &str {
+  name
+    .strip_suffix('~')
+    .and_then(|rest| rest.rsplit_once(".~"))
+    .filter(|(_, number)| !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+    .map_or(name, |(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+
+    fn started() -> DateTime<Local> {
+      Local.with_ymd_and_hms(2024, 1, 31, 9, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn no_backups_without_a_mode_or_directory() {
+      assert_eq!(BackupPolicy::new(None, "~", None, PathBuf::from("/dest"), started()), None)
+    }
+
+    #[test]
+    fn backs_up_next_to_the_destination_with_a_suffix() {
+      let policy = BackupPolicy::new(Some(BackupMode::Suffix), "~", None, PathBuf::from("/dest"), started()).unwrap();
+
+      assert_eq!(policy.backup_path(Path::new("/dest/docs/report.txt")), PathBuf::from("/dest/docs/report.txt~"))
+    }
+
+    #[test]
+    fn backs_up_with_the_start_time() {
+      let policy = BackupPolicy::new(Some(BackupMode::Timestamp), "~", None, PathBuf::from("/dest"), started()).unwrap();
+
+      assert_eq!(policy.backup_path(Path::new("/dest/report.txt")), PathBuf::from("/dest/report.txt.20240131-093000"))
+    }
+
+    #[test]
+    fn backs_up_into_a_directory_mirroring_the_destination() {
+      let policy = BackupPolicy::new(None, "~", Some(PathBuf::from("/backups")), PathBuf::from("/dest"), started()).unwrap();
+
+      assert_eq!(policy.backup_path(Path::new("/dest/docs/report.txt")), PathBuf::from("/backups/docs/report.txt"))
+    }
+
+    #[test]
+    fn recognises_backups_with_the_suffix() {
+      let policy = BackupPolicy::new(Some(BackupMode::Suffix), "~", None, PathBuf::from("/dest"), started()).unwrap();
+
+      assert!(policy.is_backup(Path::new("/dest/a.txt~")));
+      assert!(policy.is_backup(Path::new("/dest/a.txt~.~1~")));
+      assert!(!policy.is_backup(Path::new("/dest/a.txt")));
+      assert!(!policy.is_backup(Path::new("/dest/~")))
+    }
+
+    #[test]
+    fn recognises_backups_from_any_run() {
+      let policy = BackupPolicy::new(Some(BackupMode::Timestamp), "~", None, PathBuf::from("/dest"), started()).unwrap();
+
+      assert!(policy.is_backup(Path::new("/dest/a.txt.20230101-120000")));
+      assert!(policy.is_backup(Path::new("/dest/a.txt.20240131-093000.~2~")));
+      assert!(!policy.is_backup(Path::new("/dest/a.txt~")));
+      assert!(!policy.is_backup(Path::new("/dest/a.txt")))
+    }
+
+    #[test]
+    fn backups_in_a_backup_directory_are_not_next_to_destination_files() {
+      let policy = BackupPolicy::new(Some(BackupMode::Suffix), "~", Some(PathBuf::from("/backups")), PathBuf::from("/dest"), started()).unwrap();
+
+      assert!(!policy.is_backup(Path::new("/dest/a.txt~")))
+    }
+
+    #[tokio::test]
+    async fn keeps_earlier_backups() {
+      let destination = tempfile::tempdir().unwrap();
+      let destination_file = destination.path().join("report.txt");
+      std::fs::write(&destination_file, "second").unwrap();
+      std::fs::write(destination.path().join("report.txt~"), "first").unwrap();
+
+      let policy = BackupPolicy::new(Some(BackupMode::Suffix), "~", None, destination.path().to_path_buf(), started()).unwrap();
+      let backup_file = policy.back_up(&destination_file).await.unwrap();
+
+      assert_eq!(backup_file, Some(destination.path().join("report.txt~.~1~")));
+      assert_eq!(std::fs::read_to_string(destination.path().join("report.txt~")).unwrap(), "first");
+      assert_eq!(std::fs::read_to_string(destination.path().join("report.txt~.~1~")).unwrap(), "second");
+      assert_eq!(std::fs::read_to_string(&destination_file).unwrap(), "second")
+    }
+}
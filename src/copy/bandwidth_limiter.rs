@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::args::BandwidthLimit;
+
+/// The longest a copy sleeps before checking whether the limit has changed
+const MAX_SLEEP: Duration = Duration::from_millis(250);
+
+/// How often the control file is read
+const CONTROL_FILE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A token bucket shared by all file copies, so their combined rate stays under the limit.
+///
+/// Copies take tokens for the bytes they are about to copy. When there aren't enough, the bucket
+/// goes into debt and the copy waits until the debt has been paid off at the limited rate.
+#[derive(Debug)]
+pub struct BandwidthLimiter {
+  /// Bytes per second, or zero for no limit
+  limit: AtomicU64,
+  /// Changes whenever the limit does, so waiting copies can pick up the new limit
+  generation: AtomicU64,
+  bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+  tokens: f64,
+  last_refill: Instant,
+}
+
+impl BandwidthLimiter {
+
+  pub fn new(limit: Option<BandwidthLimit>) -> Self {
+    Self {
+      limit: AtomicU64::new(limit.map(|l| l.bytes_per_second()).unwrap_or(0)),
+      generation: AtomicU64::new(0),
+      bucket: Mutex::new(Bucket { tokens: 0.0, last_refill: Instant::now() }),
+    }
+  }
+
+  pub fn limit(&self) -> Option<BandwidthLimit> {
+    match self.limit.load(Ordering::Relaxed) {
+      0 => None,
+      bytes_per_second => Some(BandwidthLimit::new(bytes_per_second))
+    }
+  }
+
+  /// Changes the limit for all copies, including those currently waiting
+  pub fn set_limit(&self, limit: Option<BandwidthLimit>) {
+    let mut bucket = self.bucket.lock().unwrap();
+    bucket.tokens = 0.0;
+    bucket.last_refill = Instant::now();
+    self.limit.store(limit.map(|l| l.bytes_per_second()).unwrap_or(0), Ordering::Relaxed);
+    self.generation.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// Waits until `bytes` can be copied without going over the limit
+  pub async fn acquire(&self, bytes: u64) {
+    let generation = self.generation.load(Ordering::Relaxed);
+    let mut wait = self.take(bytes);
+
+    while !wait.is_zero() {
+      let sleep = wait.min(MAX_SLEEP);
+      tokio::time::sleep(sleep).await;
+      wait -= sleep;
+
+      if self.generation.load(Ordering::Relaxed) != generation {
+        return
+      }
+    }
+  }
+
+  /// Takes tokens for `bytes`, returning how long to wait to pay off any debt
+  fn take(&self, bytes: u64) -> Duration {
+    let mut bucket = self.bucket.lock().unwrap();
+    let rate =
+      match self.limit.load(Ordering::Relaxed) {
+        0 => return Duration::ZERO,
+        rate => rate as f64
+      };
+
+    let now = Instant::now();
+    let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+    bucket.last_refill = now;
+
+    // At most a second's worth of tokens are saved up, so idle periods don't allow long bursts
+    bucket.tokens = (bucket.tokens + elapsed * rate).min(rate) - bytes as f64;
+
+    if bucket.tokens < 0.0 {
+      Duration::from_secs_f64(-bucket.tokens / rate)
+    } else {
+      Duration::ZERO
+    }
+  }
+
+  /// Applies the limit in `control_file` whenever it changes, until the task is aborted.
+  ///
+  /// The file holds a limit such as 20MB/s, or "off" to remove the limit. Missing files and invalid limits are ignored.
+  pub async fn watch_control_file(&self, control_file: PathBuf) {
+    let mut last_contents: Option<String> = None;
+
+    loop {
+      if let Ok(contents) = tokio::fs::read_to_string(&control_file).await {
+        let contents = contents.trim().to_owned();
+        if last_contents.as_ref() != Some(&contents) {
+          if let Some(limit) = parse_control_limit(&contents) {
+            self.set_limit(limit)
+          }
+          last_contents = Some(contents);
+        }
+      }
+
+      tokio::time::sleep(CONTROL_FILE_INTERVAL).await
+    }
+  }
+}
+
+/// Parses a limit from a control file. `Some(None)` removes the limit.
+fn parse_control_limit(contents: &str) -> Option<Option<BandwidthLimit>> {
+  match contents {
+    "off" | "none" | "unlimited" => Some(None),
+    limit => BandwidthLimit::from_str(limit).ok().map(Some)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn does_not_wait_without_a_limit() {
+      let limiter = BandwidthLimiter::new(None);
+      assert_eq!(limiter.take(u64::MAX), Duration::ZERO)
+    }
+
+    #[test]
+    fn waits_for_the_debt_to_be_paid_at_the_limit() {
+      let limiter = BandwidthLimiter::new(Some(BandwidthLimit::from_str("1MB/s").unwrap()));
+      let wait = limiter.take(512 * 1024);
+
+      assert!(wait > Duration::from_millis(450) && wait <= Duration::from_millis(500), "{wait:?}")
+    }
+
+    #[test]
+    fn removing_the_limit_clears_the_debt() {
+      let limiter = BandwidthLimiter::new(Some(BandwidthLimit::from_str("1KB/s").unwrap()));
+      limiter.take(1024 * 1024);
+      limiter.set_limit(None);
+
+      assert_eq!(limiter.limit(), None);
+      assert_eq!(limiter.take(1024 * 1024), Duration::ZERO)
+    }
+
+    #[test]
+    fn parses_control_file_limits() {
+      assert_eq!(parse_control_limit("off"), Some(None));
+      assert_eq!(parse_control_limit("2MB/s"), Some(Some(BandwidthLimit::from_str("2MB/s").unwrap())));
+      assert_eq!(parse_control_limit("fast"), None)
+    }
+}
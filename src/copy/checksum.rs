@@ -0,0 +1,111 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::args::ChecksumAlgorithm;
+
+/// Incrementally calculates a content checksum.
+#[derive(Debug, Clone)]
+pub enum ChecksumHasher {
+  Blake3(Box<blake3::Hasher>),
+  Sha256(Sha256),
+}
+
+impl ChecksumHasher {
+
+  pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+    match algorithm {
+      ChecksumAlgorithm::Blake3 => ChecksumHasher::Blake3(Box::new(blake3::Hasher::new())),
+      ChecksumAlgorithm::Sha256 => ChecksumHasher::Sha256(Sha256::new()),
+    }
+  }
+
+  pub fn update(&mut self, bytes: &[u8]) {
+    match self {
+      ChecksumHasher::Blake3(hasher) => {
+        hasher.update(bytes);
+      },
+      ChecksumHasher::Sha256(hasher) => hasher.update(bytes),
+    }
+  }
+
+  /// Updates the checksum with `length` zero bytes, as read from a hole in a sparse file.
+  pub fn update_zeros(&mut self, length: u64) {
+    let zeros = [0_u8; 64 * 1024];
+    let mut remaining = length;
+    while remaining > 0 {
+      let chunk = remaining.min(zeros.len() as u64) as usize;
+      self.update(&zeros[..chunk]);
+      remaining -= chunk as u64;
+    }
+  }
+
+  /// The checksum as a lowercase hex string
+  pub fn finalize(self) -> String {
+    match self {
+      ChecksumHasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+      ChecksumHasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+    }
+  }
+}
+
+/// Calculates the checksum of a file as a lowercase hex string.
+pub fn file_checksum<P: AsRef<Path>>(path: P, algorithm: ChecksumAlgorithm) -> io::Result<String> {
+  let mut hasher = ChecksumHasher::new(algorithm);
+  let mut file = File::open(path)?;
+  let mut buffer = vec![0; 1024 * 1024];
+
+  loop {
+    let bytes_read = file.read(&mut buffer)?;
+    if bytes_read == 0 {
+      return Ok(hasher.finalize())
+    }
+
+    hasher.update(&buffer[..bytes_read])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn calculates_blake3_checksum() {
+      let mut hasher = ChecksumHasher::new(ChecksumAlgorithm::Blake3);
+      hasher.update(b"abc");
+      assert_eq!(hasher.finalize(), "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85")
+    }
+
+    #[test]
+    fn calculates_sha256_checksum() {
+      let mut hasher = ChecksumHasher::new(ChecksumAlgorithm::Sha256);
+      hasher.update(b"abc");
+      assert_eq!(hasher.finalize(), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+    }
+
+    #[test]
+    fn checksum_is_independent_of_chunking() {
+      let mut whole = ChecksumHasher::new(ChecksumAlgorithm::Sha256);
+      whole.update(b"hello world");
+
+      let mut chunked = ChecksumHasher::new(ChecksumAlgorithm::Sha256);
+      chunked.update(b"hello");
+      chunked.update(b" world");
+
+      assert_eq!(whole.finalize(), chunked.finalize())
+    }
+
+    #[test]
+    fn zeros_hash_the_same_as_zero_bytes() {
+      let mut bytes = ChecksumHasher::new(ChecksumAlgorithm::Blake3);
+      bytes.update(&vec![0; 100_000]);
+
+      let mut zeros = ChecksumHasher::new(ChecksumAlgorithm::Blake3);
+      zeros.update_zeros(100_000);
+
+      assert_eq!(bytes.finalize(), zeros.finalize())
+    }
+}
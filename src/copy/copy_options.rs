@@ -0,0 +1,104 @@
+use crate::args::{BufferSize, ChecksumAlgorithm, PreserveAttribute, RangeThreshold, RetryBackoff, SyncMode, VerifyMode};
+use std::sync::Arc;
+
+use crate::cli::Args;
+use super::{BackupPolicy, BandwidthLimiter, RetryPolicy};
+
+/// Settings that apply to every file copy in a run.
+#[derive(Debug, Clone)]
+pub struct CopyOptions {
+  buffer_size: BufferSize,
+  resume: bool,
+  verify: VerifyMode,
+  sync: SyncMode,
+  checksum: ChecksumAlgorithm,
+  preserve: Vec<PreserveAttribute>,
+  xattr_namespaces: Vec<String>,
+  accelerate: bool,
+  retry_policy: RetryPolicy,
+  bandwidth_limiter: Arc<BandwidthLimiter>,
+  move_sources: bool,
+  backup: Option<BackupPolicy>,
+  ranges: u8,
+  range_threshold: RangeThreshold,
+}
+
+impl CopyOptions {
+
+  pub fn buffer_size(&self) -> &BufferSize {
+    &self.buffer_size
+  }
+
+  pub fn resume(&self) -> bool {
+    self.resume
+  }
+
+  /// The algorithm to verify copied files with, if checksum verification is enabled.
+  pub fn checksum_verification(&self) -> Option<ChecksumAlgorithm> {
+    match self.verify {
+      VerifyMode::Size => None,
+      VerifyMode::Checksum => Some(self.checksum),
+    }
+  }
+
+  pub fn sync(&self) -> SyncMode {
+    self.sync
+  }
+
+  pub fn preserve(&self) -> &[PreserveAttribute] {
+    &self.preserve
+  }
+
+  pub fn xattr_namespaces(&self) -> &[String] {
+    &self.xattr_namespaces
+  }
+
+  pub fn accelerate(&self) -> bool {
+    self.accelerate
+  }
+
+  pub fn retry_policy(&self) -> &RetryPolicy {
+    &self.retry_policy
+  }
+
+  /// Whether source files are deleted once they have been copied
+  pub fn move_sources(&self) -> bool {
+    self.move_sources
+  }
+
+  /// How many byte ranges to copy a file as at once, if it is big enough to be split
+  pub fn range_count(&self, file_size: u64) -> Option<u64> {
+    (self.ranges > 1 && file_size >= self.range_threshold.bytes()).then_some(self.ranges as u64)
+  }
+
+  /// How replaced destination files are backed up, if they are
+  pub fn backup(&self) -> Option<&BackupPolicy> {
+    self.backup.as_ref()
+  }
+
+  /// Shared by all copies made with these options
+  pub fn bandwidth_limiter(&self) -> &Arc<BandwidthLimiter> {
+    &self.bandwidth_limiter
+  }
+}
+
+impl From<&Args> for CopyOptions {
+  fn from(args: &Args) -> Self {
+    Self {
+      buffer_size: args.buffer_size.clone().unwrap_or(BufferSize::DEFAULT_BUFFER_SIZE),
+      resume: args.resume,
+      verify: args.verify,
+      sync: args.sync,
+      checksum: args.checksum,
+      preserve: args.preserve.clone(),
+      xattr_namespaces: args.xattr_namespace.clone(),
+      accelerate: args.accelerate,
+      move_sources: args.move_sources,
+      backup: BackupPolicy::new(args.backup, &args.backup_suffix, args.backup_dir.clone(), args.destination_dir.clone(), chrono::Local::now()),
+      ranges: args.ranges,
+      range_threshold: args.range_threshold.clone().unwrap_or(RangeThreshold::DEFAULT_RANGE_THRESHOLD),
+      bandwidth_limiter: Arc::new(BandwidthLimiter::new(args.bwlimit.clone())),
+      retry_policy: RetryPolicy::new(args.retries, args.retry_backoff.clone().unwrap_or(RetryBackoff::DEFAULT_RETRY_BACKOFF)),
+    }
+  }
+}
@@ -1,31 +1,39 @@
+use std::cmp::min;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use indicatif::MultiProgress;
-use tokio::fs::{DirBuilder, File};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::io::SeekFrom;
+use tokio::fs::{DirBuilder, File, OpenOptions};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::task::JoinSet;
 
 use crate::monitor::MonitorMux;
 use crate::progress::MyProgressBar;
-use crate::model::{SizeComparison, R, CopyError};
-use crate::args::BufferSize;
-use super::SourceFile;
+use crate::args::{ChecksumAlgorithm, PreserveAttribute, SyncMode};
+use crate::model::{ChecksumComparison, CopyMethod, RetryAttempt, SizeComparison, R, CopyError};
+use crate::plan::{CopyAction, PlanEntry};
+use super::{
+  copy_extended_attributes, copy_range, file_checksum, is_transient, is_unsupported, next_data_region, preserve_attributes, reflink, BandwidthLimiter, ChecksumHasher, CopyOptions, SourceFile
+};
 
 #[derive(Debug, Clone)]
 pub struct FileCopy {
   source_file: SourceFile,
-  destination_dir_path: PathBuf,
+  destination_file: PathBuf,
+  action: CopyAction,
   progress_bar: MyProgressBar,
 }
 
 impl FileCopy {
 
-  pub fn new<D: AsRef<Path>>(source_file: SourceFile, destination_dir_path: D, multi: &MultiProgress) -> Self {
-    let destination_dir = destination_dir_path.as_ref().to_path_buf();
-
+  pub fn new(plan_entry: PlanEntry, multi: &MultiProgress) -> Self {
     let progress_bar = MyProgressBar::new(multi);
 
     Self {
-      source_file,
-      destination_dir_path: destination_dir,
+      source_file: plan_entry.source_file().clone(),
+      destination_file: plan_entry.destination().to_path_buf(),
+      action: plan_entry.action().clone(),
       progress_bar,
     }
   }
@@ -34,24 +42,155 @@ impl FileCopy {
     self.source_file.file_name()
   }
 
+  /// Relative to the source directory
+  pub fn source_relative_path(&self) -> String {
+    self.source_file.relative_path()
+  }
+
   pub fn destination_file(&self) -> PathBuf {
-    self.destination_dir_path.join(self.source_file.relative_path())
+    self.destination_file.clone()
+  }
+
+  /// The hidden file data is written to, before it is renamed to the destination file.
+  ///
+  /// It lives next to the destination file so the rename stays on the same filesystem.
+  pub fn partial_file(&self) -> PathBuf {
+    let file_name =
+      self
+        .destination_file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    self.destination_file.with_file_name(format!(".{file_name}.coop-partial"))
   }
 
-  pub async fn copy<'a>(self, buffer: BufferSize, mux: MonitorMux) -> R<()> {
+  pub async fn copy(self, options: CopyOptions, mux: MonitorMux) -> R<()> {
     let progress_bar = &self.progress_bar;
     progress_bar.tick();
     progress_bar.set_prefix(self.source_file_name());
-    let file_name = &self.source_file.file_name();
+    let file_name = &self.source_file.relative_path();
+
+    if let CopyAction::Skip(reason) = &self.action {
+      mux.send_skipped(file_name, self.source_file.size(), reason.clone(), progress_bar).await;
+      return Ok(())
+    }
+
+    // A rename is all a move needs on the same filesystem
+    if options.move_sources() && matches!(self.action, CopyAction::Copy) && self.move_by_rename(&options, &mux).await.is_ok() {
+      self.sync_destination_directory(&options, &mux).await?;
+      return Self::succeed(&mux, progress_bar, self.source_relative_path().as_str(), self.source_file.size()).await
+    }
+
+    let result =
+      match &self.action {
+        CopyAction::Symlink(target) => self.copy_symlink(target, &options, &mux).await,
+        CopyAction::HardLink(linked_file) => self.copy_hard_link(linked_file, &options, &mux).await,
+        _ => self.copy_file(&options, &mux).await
+      };
+
+    // Keep partial files around for a later run to resume from, if asked to
+    if result.is_err() && !options.resume() {
+      let _ = tokio::fs::remove_file(self.partial_file()).await;
+    }
+
+    // Each file finishes exactly once: failures have already been sent by whatever failed
+    let copied_bytes = result?;
+
+    // Only delete sources that have been copied, and verified if enabled, successfully
+    if options.move_sources() {
+      self.delete_source(&mux).await
+    }
+
+    Self::succeed(&mux, progress_bar, self.source_relative_path().as_str(), copied_bytes).await
+  }
+
+  /// Renames the source file to its destination. This fails, without sending any failure events,
+  /// when they are on different filesystems, so the file can be copied instead.
+  ///
+  /// Once renamed, the file can't be copied any more, so anything that can fail afterwards is left to the caller.
+  async fn move_by_rename(&self, options: &CopyOptions, mux: &MonitorMux) -> R<()> {
+    let progress_bar = &self.progress_bar;
+    let destination_file = self.destination_file();
+
+    // Renaming a followed symlink would move the link instead of the file it links to,
+    // so they are copied, and the link is only deleted once its copy has been verified
+    match tokio::fs::symlink_metadata(self.source_file.full_path()).await {
+      Ok(metadata) if !metadata.is_symlink() => (),
+      _ => return Err(())
+    }
+
+    let Some(parent) = destination_file.parent() else {
+      return Err(())
+    };
+    tokio::fs::create_dir_all(parent).await.map_err(|_| ())?;
+
+    // Check before backing up, so files that are copied instead are only backed up once, by the copy
+    if !same_filesystem(self.source_file.full_path(), parent).await {
+      return Err(())
+    }
+
+    // A backup that fails here is made, and reported, again by the copy
+    if let Some(backup) = options.backup() {
+      if let Some(backup_file) = backup.back_up(&destination_file).await.map_err(|_| ())? {
+        mux.send_backed_up_destination(&self.source_file.relative_path(), backup_file, progress_bar).await
+      }
+    }
+
+    tokio::fs::rename(self.source_file.full_path(), &destination_file).await.map_err(|_| ())?;
+
+    // Left behind by an earlier copy that was meant to be resumed
+    let _ = tokio::fs::remove_file(self.partial_file()).await;
+
+    mux.send_moved_by_rename(self.source_file.size(), progress_bar).await;
+    Ok(())
+  }
+
+  async fn delete_source(&self, mux: &MonitorMux) {
+    let progress_bar = &self.progress_bar;
+
+    match tokio::fs::remove_file(self.source_file.full_path()).await {
+      Ok(_) => mux.send_deleted_source(progress_bar).await,
+      Err(e) => mux.send_could_not_delete_source(&self.source_file.relative_path(), <std::io::Error as Into<CopyError>>::into(e), progress_bar).await,
+    }
+  }
+
+  /// Copies the file into place, returning how many bytes it has.
+  async fn copy_file(&self, options: &CopyOptions, mux: &MonitorMux) -> R<u64> {
+    let progress_bar = &self.progress_bar;
+    let file_name = &self.source_file.relative_path();
 
     mux.send_not_started(progress_bar).await;
 
-    let mut source_file = Self::open_source_file(&self.source_file.full_path(), &mux, progress_bar).await?;
+    let mut source_file = Self::open_source_file(file_name, self.source_file.full_path(), mux, progress_bar).await?;
     let file_size = self.source_file.size();
-    Self::create_destination_path(&self.destination_file(), &mux, progress_bar).await?;
-    let mut destination_file = Self::create_destination_file(&self.destination_file(), &mux, progress_bar).await?;
+    let partial_file = self.partial_file();
+    Self::create_destination_path(file_name, &self.destination_file(), mux, progress_bar).await?;
+
+    let buffer = options.buffer_size();
+    let resume_offset =
+      if options.resume() {
+        Self::get_resume_offset(&mut source_file, &partial_file, file_size, buffer.bytes()).await
+      } else {
+        0
+      };
+
+    let destination_file =
+      if resume_offset > 0 {
+        let df = Self::open_destination_file_at(file_name, &partial_file, resume_offset, mux, progress_bar).await?;
+        Self::seek_source_file(file_name, &mut source_file, resume_offset, mux, progress_bar).await?;
+        df
+      } else {
+        Self::create_destination_file(file_name, &partial_file, mux, progress_bar).await?
+      };
 
     progress_bar.set_file_size(file_size);
+
+    if resume_offset > 0 {
+      progress_bar.resume_from(resume_offset);
+      mux.send_resumed(resume_offset, progress_bar).await;
+    }
+
     let buf_size =
       if file_size <= buffer.bytes() {
         file_size as usize // If file_size can be contained in buffer, then use that as the buffer size and don't chunk
@@ -59,28 +198,503 @@ impl FileCopy {
         buffer.bytes() as usize // If the file_size can't be contained in buffer, then chunk by buffer size
       };
 
-    let mut buffer = vec![0; buf_size];
+    // One buffer is written while the next chunk is read into the other
+    let mut buffers = [vec![0; buf_size], vec![0; buf_size]];
+
+    let copy_method =
+      if options.accelerate() {
+        Self::accelerated_copy_method(&source_file, &destination_file, resume_offset, file_size, mux, progress_bar).await
+      } else {
+        CopyMethod::ReadWrite
+      };
+
+    // Large files are split into ranges, unless they have been reflinked or are being resumed
+    let range_count =
+      options
+        .range_count(file_size)
+        .filter(|_| resume_offset == 0 && copy_method != CopyMethod::Reflink);
+
+    // Hash the source as it's copied so checksum verification doesn't need to read it again.
+    // This is only possible when the whole source is read in order, which isn't the case for resumed, accelerated or ranged copies.
+    let source_hasher =
+      options
+        .checksum_verification()
+        .filter(|_| resume_offset == 0 && copy_method == CopyMethod::ReadWrite && range_count.is_none())
+        .map(ChecksumHasher::new);
+
+    let (mut destination_file, source_hasher) =
+      match range_count {
+        Some(range_count) => {
+          self.copy_ranges(source_file, &destination_file, range_count, file_size, options, mux).await?;
+          (destination_file, None)
+        },
+        None => {
+          // A reflink has already copied everything
+          let copied_up_to = if copy_method == CopyMethod::Reflink { file_size } else { resume_offset };
+
+          let transfer =
+            Transfer {
+              source_file,
+              destination_file,
+              position: copied_up_to,
+              data_end: copied_up_to,
+              copy_method,
+              source_hasher,
+              reopen: false,
+            };
+
+          self.copy_sequentially(transfer, &mut buffers, file_size, options, mux).await?
+        }
+      };
+
+    Self::complete_file_copy(file_name, &mut destination_file, file_size, options.sync(), mux, progress_bar).await?;
+    drop(destination_file);
+
+    if let Some(algorithm) = options.checksum_verification() {
+      Self::verify_checksum(file_name, source_hasher, algorithm, self.source_file.full_path(), &partial_file, mux, progress_bar).await?;
+    }
+
+    if !options.preserve().is_empty() {
+      Self::copy_file_extended_attributes(file_name, self.source_file.full_path(), &partial_file, options, mux, progress_bar).await;
+      Self::preserve_file_attributes(file_name, self.source_file.full_path(), &partial_file, options.preserve(), mux, progress_bar).await?;
+    }
+
+    self.back_up_destination(options, mux).await?;
+    Self::rename_partial_file(file_name, &partial_file, &self.destination_file(), mux, progress_bar).await?;
+
+    self.sync_destination_directory(options, mux).await?;
+    Ok(file_size)
+  }
+
+  /// Copies the file's data in order from `transfer.position`, retrying transient failures.
+  /// Returns the destination file and the hash of the source, if it was hashed while copying.
+  async fn copy_sequentially(&self, mut transfer: Transfer, buffers: &mut [Vec<u8>; 2], file_size: u64, options: &CopyOptions, mux: &MonitorMux) -> R<(File, Option<ChecksumHasher>)> {
+    let progress_bar = &self.progress_bar;
+    let file_name = &self.source_file.relative_path();
+    let retry_policy = options.retry_policy();
+    let mut attempt = 0;
+
+    loop {
+      let error =
+        match self.transfer_data(&mut transfer, buffers, file_size, options.bandwidth_limiter(), mux).await {
+          Ok(()) => return Ok((transfer.destination_file, transfer.source_hasher)),
+          Err(error) => error
+        };
+
+      if attempt >= retry_policy.retries() || !is_transient(error.io_error()) {
+        error.send(file_name, mux, progress_bar).await;
+        return Err(())
+      }
+
+      attempt += 1;
+      mux.send_retrying(file_name, RetryAttempt::new(attempt, retry_policy.retries()), CopyError::from(error.io_error()), progress_bar).await;
+      tokio::time::sleep(retry_policy.delay(attempt)).await;
+      transfer.reopen = true;
+    }
+  }
+
+  /// Copies a large file as several byte ranges at once, each with its own positional reads and writes.
+  ///
+  /// The partial file is extended to the full size first, so the ranges can be written in any order,
+  /// and holes in the source are left as holes by not writing them.
+  /// Ranges that fail with a transient error are retried from where they got to, with both files reopened,
+  /// while the others are left finished.
+  async fn copy_ranges(&self, source_file: File, destination_file: &File, range_count: u64, file_size: u64, options: &CopyOptions, mux: &MonitorMux) -> R<()> {
+    let progress_bar = &self.progress_bar;
+    let file_name = &self.source_file.relative_path();
+
+    let destination =
+      match destination_file.set_len(file_size).await {
+        Ok(_) => destination_file.try_clone().await,
+        Err(e) => Err(e)
+      };
+
+    let mut destination =
+      match destination {
+        Ok(destination) => Arc::new(destination.into_std().await),
+        Err(e) => {
+          mux.send_write_to_destination_failed(file_name, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+          return Err(())
+        }
+      };
+
+    let mut source = Arc::new(source_file.into_std().await);
+    let retry_policy = options.retry_policy();
+    let mut ranges = split_ranges(file_size, range_count);
+    let mut attempt = 0;
 
     loop {
-      let bytes_read = Self::read_to_buffer(file_name, &mut source_file, &mut buffer, &mux, progress_bar).await?;
+      let reopened =
+        if attempt == 0 {
+          Ok(())
+        } else {
+          self.reopen_range_files(&mut source, &mut destination).await
+        };
+
+      let error =
+        match reopened {
+          Ok(()) =>
+            match Self::copy_remaining_ranges(&source, &destination, &mut ranges, options, mux, progress_bar).await {
+              Some(error) => error,
+              None => return Ok(())
+            },
+          Err(error) => error
+        };
+
+      if attempt >= retry_policy.retries() || !is_transient(error.io_error()) {
+        error.send(file_name, mux, progress_bar).await;
+        return Err(())
+      }
 
-      if bytes_read == 0 {
-        Self::complete_file_copy(file_name, &mut destination_file, file_size, &mux, progress_bar, self.source_file_name().as_str()).await?;
+      attempt += 1;
+      mux.send_retrying(file_name, RetryAttempt::new(attempt, retry_policy.retries()), CopyError::from(error.io_error()), progress_bar).await;
+      tokio::time::sleep(retry_policy.delay(attempt)).await;
+    }
+  }
+
+  /// Copies all of `ranges` at once, leaving in it what is left of any that failed.
+  /// Returns the error to retry or fail with, which is the first one that can't be retried, if there is one.
+  async fn copy_remaining_ranges(
+    source: &Arc<std::fs::File>,
+    destination: &Arc<std::fs::File>,
+    ranges: &mut Vec<Range<u64>>,
+    options: &CopyOptions,
+    mux: &MonitorMux,
+    progress_bar: &MyProgressBar
+  ) -> Option<TransferError> {
+    let mut range_copies = JoinSet::new();
+
+    for range in ranges.drain(..) {
+      range_copies.spawn(
+        Self::copy_byte_range(
+          source.clone(),
+          destination.clone(),
+          range,
+          options.buffer_size().bytes(),
+          options.bandwidth_limiter().clone(),
+          mux.clone(),
+          progress_bar.clone()
+        )
+      );
+    }
+
+    let mut error: Option<TransferError> = None;
+
+    while let Some(result) = range_copies.join_next().await {
+      let (remaining, range_error) =
+        match result {
+          Ok((_, Ok(()))) => continue,
+          Ok((remaining, Err(e))) => (Some(remaining), e),
+          // A range that panicked can't be continued, which the error not being transient makes sure of
+          Err(e) => (None, TransferError::Write(std::io::Error::other(e)))
+        };
+
+      ranges.extend(remaining);
+
+      if error.as_ref().is_none_or(|e| is_transient(e.io_error())) {
+        error = Some(range_error)
+      }
+    }
+
+    error
+  }
+
+  /// Reopens both files before failed ranges are retried, as their handles may no longer be usable.
+  ///
+  /// Ranges are written at their own offsets, so unlike a sequential copy nothing needs to be truncated.
+  async fn reopen_range_files(&self, source: &mut Arc<std::fs::File>, destination: &mut Arc<std::fs::File>) -> Result<(), TransferError> {
+    let source_file = File::open(self.source_file.full_path()).await.map_err(TransferError::Read)?;
+    let destination_file =
+      OpenOptions::new()
+        .write(true)
+        .open(self.partial_file())
+        .await
+        .map_err(TransferError::Write)?;
+
+    *source = Arc::new(source_file.into_std().await);
+    *destination = Arc::new(destination_file.into_std().await);
+
+    Ok(())
+  }
+
+  /// Copies one byte range a chunk at a time, returning what is left of it along with any failure.
+  ///
+  /// Holes in the range are skipped, as the partial file already has a hole everywhere nothing is written.
+  async fn copy_byte_range(
+    source: Arc<std::fs::File>,
+    destination: Arc<std::fs::File>,
+    mut range: Range<u64>,
+    chunk_size: u64,
+    bandwidth_limiter: Arc<BandwidthLimiter>,
+    mux: MonitorMux,
+    progress_bar: MyProgressBar
+  ) -> (Range<u64>, Result<(), TransferError>) {
+    let mut data_end = range.start;
+
+    while !range.is_empty() {
+      if range.start == data_end {
+        // Only the data region is looked for, so moving the shared file position doesn't matter to the positional reads
+        let data_region = next_data_region(&*source, range.start, range.end).unwrap_or(range.end..range.end);
+        let hole = data_region.start - range.start;
+
+        if hole > 0 {
+          mux.send_skipped_hole(hole, &progress_bar).await;
+        }
+
+        range.start = data_region.start;
+        data_end = data_region.end;
+        continue
+      }
+
+      let length = min(chunk_size, data_end - range.start);
+      bandwidth_limiter.acquire(length).await;
+
+      let (source, destination, offset) = (source.clone(), destination.clone(), range.start);
+      let result =
+        tokio::task::spawn_blocking(move || copy_chunk_at(&source, &destination, offset, length))
+          .await
+          .unwrap_or_else(|e| Err(TransferError::Write(std::io::Error::other(e))));
+
+      match result {
+        Ok(bytes_copied) => {
+          mux.send_copy_in_progress(bytes_copied, CopyMethod::ParallelRanges, &progress_bar).await;
+          range.start += bytes_copied
+        },
+        Err(e) => return (range, Err(e))
+      }
+    }
+
+    (range, Ok(()))
+  }
+
+  /// Copies the file's data from `transfer.position` to the end of the source.
+  ///
+  /// Holes in sparse files are skipped instead of copied, so only data regions are read.
+  async fn transfer_data(&self, transfer: &mut Transfer, buffers: &mut [Vec<u8>; 2], file_size: u64, bandwidth_limiter: &BandwidthLimiter, mux: &MonitorMux) -> Result<(), TransferError> {
+    let progress_bar = &self.progress_bar;
+
+    if transfer.reopen {
+      self.reopen_files(transfer).await?;
+    }
+
+    loop {
+      if transfer.position == transfer.data_end {
+        let data_region = next_data_region(&transfer.source_file, transfer.position, file_size).unwrap_or(file_size..file_size);
+        Self::skip_hole(transfer, transfer.position..data_region.start, mux, progress_bar).await?;
+        transfer.position = data_region.start;
+        transfer.data_end = data_region.end;
+      }
+
+      // Nothing is left to copy after the last data region
+      if transfer.position == transfer.data_end {
+        return Ok(())
+      }
+
+      if transfer.copy_method == CopyMethod::CopyFileRange && transfer.position < transfer.data_end {
+        let length = min(buffers[0].len() as u64, transfer.data_end - transfer.position);
+        bandwidth_limiter.acquire(length).await;
+        match Self::copy_chunk_in_kernel(transfer, length, mux, progress_bar).await? {
+          Some(bytes_copied) => {
+            transfer.position += bytes_copied;
+            continue
+          },
+          None => {
+            // copy_file_range doesn't move either file's position, so the buffered copy has to start from the right place
+            transfer.copy_method = CopyMethod::ReadWrite;
+            transfer.source_file.seek(SeekFrom::Start(transfer.position)).await.map_err(TransferError::Read)?;
+            transfer.destination_file.seek(SeekFrom::Start(transfer.position)).await.map_err(TransferError::Write)?;
+          }
+        }
+      }
+
+      if !Self::pipe_data_region(transfer, buffers, bandwidth_limiter, mux, progress_bar).await? {
+        // The source ended early, which the size comparison reports
         return Ok(())
       }
+    }
+  }
+
+  /// Copies the rest of the current data region with reads and writes, reading the next chunk from the source
+  /// while the last one is written to the destination, so neither sits idle waiting for the other.
+  ///
+  /// Returns false if the source ended before the end of the region.
+  async fn pipe_data_region(transfer: &mut Transfer, buffers: &mut [Vec<u8>; 2], bandwidth_limiter: &BandwidthLimiter, mux: &MonitorMux, progress_bar: &MyProgressBar) -> Result<bool, TransferError> {
+    let [current, next] = buffers;
+    let chunk_size = current.len() as u64;
+
+    let read_length = min(chunk_size, transfer.data_end - transfer.position) as usize;
+    let mut bytes_read = Self::read_to_buffer(&mut transfer.source_file, &mut current[..read_length]).await?;
+
+    while bytes_read > 0 {
+      let next_read_length = min(chunk_size, transfer.data_end - transfer.position - bytes_read) as usize;
+      let bytes = &current[..bytes_read as usize];
+      bandwidth_limiter.acquire(bytes_read).await;
+
+      let (written, next_bytes_read) =
+        tokio::join!(
+          Self::write_to_destination(&mut transfer.destination_file, bytes, mux, progress_bar),
+          Self::read_to_buffer(&mut transfer.source_file, &mut next[..next_read_length])
+        );
+
+      // Only what was written counts as copied, so a failed read ahead is continued from here
+      written?;
+      transfer.position += bytes_read;
+
+      if let Some(hasher) = transfer.source_hasher.as_mut() {
+        hasher.update(bytes)
+      }
+
+      bytes_read = next_bytes_read?;
+      std::mem::swap(current, next);
+    }
+
+    Ok(transfer.position == transfer.data_end)
+  }
+
+  /// Reopens both files after a failure, as their handles may no longer be usable.
+  ///
+  /// Writes that failed may not have reached the partial file, so the copy continues from
+  /// wherever the partial file ends, if that's before the expected position.
+  async fn reopen_files(&self, transfer: &mut Transfer) -> Result<(), TransferError> {
+    let mut source_file = File::open(self.source_file.full_path()).await.map_err(TransferError::Read)?;
+    let mut destination_file =
+      OpenOptions::new()
+        .write(true)
+        .open(self.partial_file())
+        .await
+        .map_err(TransferError::Write)?;
+
+    let written = destination_file.metadata().await.map_err(TransferError::Write)?.len();
+    let offset = min(transfer.position, written);
+
+    destination_file.set_len(offset).await.map_err(TransferError::Write)?;
+    destination_file.seek(SeekFrom::Start(offset)).await.map_err(TransferError::Write)?;
+    source_file.seek(SeekFrom::Start(offset)).await.map_err(TransferError::Read)?;
+
+    if offset < transfer.position {
+      // The hash has already seen data that will be copied again
+      transfer.source_hasher = None;
+      self.progress_bar.resume_from(offset);
+    }
+
+    transfer.source_file = source_file;
+    transfer.destination_file = destination_file;
+    transfer.position = offset;
+    transfer.data_end = offset;
+    transfer.reopen = false;
+
+    Ok(())
+  }
+
+  /// Picks the fastest way to copy a file's data, trying to reflink the whole file first.
+  ///
+  /// Reflinks replace the destination's content, so they are only tried for copies starting from zero.
+  async fn accelerated_copy_method(source_file: &File, destination_file: &File, resume_offset: u64, file_size: u64, mux: &MonitorMux, progress_bar: &MyProgressBar) -> CopyMethod {
+    if resume_offset == 0 && file_size > 0 && reflink(source_file, destination_file).await.is_ok() {
+      mux.send_copy_in_progress(file_size, CopyMethod::Reflink, progress_bar).await;
+      CopyMethod::Reflink
+    } else {
+      CopyMethod::CopyFileRange
+    }
+  }
 
-      Self::write_to_destination(file_name, &mut destination_file, &buffer[..bytes_read as usize], &mux, progress_bar).await?;
+  /// Copies the next chunk with copy_file_range.
+  ///
+  /// Returns `None` when the rest of the file has to be copied with reads and writes instead,
+  /// because copy_file_range is unsupported or the source ended early.
+  async fn copy_chunk_in_kernel(transfer: &Transfer, length: u64, mux: &MonitorMux, progress_bar: &MyProgressBar) -> Result<Option<u64>, TransferError> {
+    match copy_range(&transfer.source_file, &transfer.destination_file, transfer.position, length).await {
+      Ok(0) => Ok(None),
+      Ok(bytes_copied) => {
+        mux.send_copy_in_progress(bytes_copied, CopyMethod::CopyFileRange, progress_bar).await;
+        Ok(Some(bytes_copied))
+      },
+      Err(e) if is_unsupported(&e) => Ok(None),
+      Err(e) => Err(TransferError::Write(e))
     }
   }
 
-  async fn open_source_file<P: AsRef<Path> + Clone>(file: P, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<File> {
-      match File::open(file.as_ref()).await {
+  /// Moves both files past a hole, leaving the same hole in the destination.
+  ///
+  /// The source position is always restored, as looking for holes moves it.
+  async fn skip_hole(transfer: &mut Transfer, hole: Range<u64>, mux: &MonitorMux, progress_bar: &MyProgressBar) -> Result<(), TransferError> {
+    transfer.source_file.seek(SeekFrom::Start(hole.end)).await.map_err(TransferError::Read)?;
+
+    if hole.is_empty() {
+      return Ok(())
+    }
+
+    // Extending the file leaves a hole, which also covers holes at the end of the file
+    transfer.destination_file.set_len(hole.end).await.map_err(TransferError::Write)?;
+    transfer.destination_file.seek(SeekFrom::Start(hole.end)).await.map_err(TransferError::Write)?;
+
+    if let Some(hasher) = transfer.source_hasher.as_mut() {
+      hasher.update_zeros(hole.end - hole.start)
+    }
+
+    mux.send_skipped_hole(hole.end - hole.start, progress_bar).await;
+
+    Ok(())
+  }
+
+  /// Creates the symlink under the partial file name first, so an existing destination is replaced in one step.
+  async fn copy_symlink(&self, target: &Path, options: &CopyOptions, mux: &MonitorMux) -> R<u64> {
+    let progress_bar = &self.progress_bar;
+    let file_name = &self.source_file.relative_path();
+    let partial_file = self.partial_file();
+
+    mux.send_not_started(progress_bar).await;
+    Self::create_destination_path(file_name, &self.destination_file(), mux, progress_bar).await?;
+
+    let _ = tokio::fs::remove_file(&partial_file).await;
+
+    match symlink(target, &partial_file).await {
+      Ok(_) => mux.send_created_symlink(progress_bar).await,
+      Err(e) => {
+        mux.send_could_not_create_symlink(file_name, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        return Err(())
+      }
+    }
+
+    self.back_up_destination(options, mux).await?;
+    Self::rename_partial_file(file_name, &partial_file, &self.destination_file(), mux, progress_bar).await?;
+    self.sync_destination_directory(options, mux).await?;
+    Ok(0)
+  }
+
+  /// Links to a file that has already been copied, instead of copying the same data again.
+  async fn copy_hard_link(&self, linked_file: &Path, options: &CopyOptions, mux: &MonitorMux) -> R<u64> {
+    let progress_bar = &self.progress_bar;
+    let file_name = &self.source_file.relative_path();
+    let partial_file = self.partial_file();
+
+    mux.send_not_started(progress_bar).await;
+    Self::create_destination_path(file_name, &self.destination_file(), mux, progress_bar).await?;
+
+    let _ = tokio::fs::remove_file(&partial_file).await;
+
+    match tokio::fs::hard_link(linked_file, &partial_file).await {
+      Ok(_) => mux.send_created_hard_link(progress_bar).await,
+      Err(e) => {
+        mux.send_could_not_create_hard_link(file_name, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        return Err(())
+      }
+    }
+
+    self.back_up_destination(options, mux).await?;
+    Self::rename_partial_file(file_name, &partial_file, &self.destination_file(), mux, progress_bar).await?;
+    self.sync_destination_directory(options, mux).await?;
+    Ok(0)
+  }
+
+  async fn open_source_file(file_name: &str, file: &Path, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<File> {
+      match File::open(file).await {
         Ok(file) => {
           mux.send_opened_source_file(progress_bar).await;
           Ok(file)
         },
         Err(e) => {
-          mux.send_could_not_read_source_file(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+          mux.send_could_not_read_source_file(file_name, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
           Err(())
         }
       }
@@ -103,8 +717,8 @@ impl FileCopy {
   }
 
 
-  async fn create_destination_path<P: AsRef<Path> + Clone>(destination_file: P, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<()> {
-    if let Some(parent_path) = destination_file.as_ref().parent() {
+  async fn create_destination_path(file_name: &str, destination_file: &Path, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<()> {
+    if let Some(parent_path) = destination_file.parent() {
     // check if it exists, if not create it
      if !parent_path.exists() {
        let result =
@@ -114,7 +728,7 @@ impl FileCopy {
           .await;
 
         if let Err(e) = result {
-          mux.send_could_not_create_destination_directory(destination_file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+          mux.send_could_not_create_destination_directory(file_name, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
           return Err(());
         }
      }
@@ -123,27 +737,64 @@ impl FileCopy {
     Ok(())
   }
 
-  async fn create_destination_file<P: AsRef<Path> + Clone>(destination_file: P, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<File> {
-    match File::create(destination_file.as_ref()).await {
+  /// Creates the file data is written to, which is reported as the user's file, `file_name`, if it fails.
+  async fn create_destination_file(file_name: &str, destination_file: &Path, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<File> {
+    match File::create(destination_file).await {
       Ok(df) => {
         mux.send_created_destination_file(progress_bar).await;
         Ok(df)
       },
       Err(e) => {
-        mux.send_could_not_create_destination_file(destination_file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        mux.send_could_not_create_destination_file(file_name, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
         Err(())
       }
     }
   }
 
-  async fn read_to_buffer(file: &str, source_file: &mut File, buffer: &mut [u8], mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<u64> {
-    let bytes_read_result =
-      source_file
-        .read(buffer)
-        .await;
+  /// Finds the offset a copy can be resumed from.
+  ///
+  /// A partial destination file is only trusted if it is smaller than the source and its last chunk
+  /// (up to one buffer's worth) matches the same range in the source. Anything else starts again from zero.
+  ///
+  /// Ranged copies extend the partial file to the source's size before writing their ranges in any order,
+  /// so a partial file that is already that size can't be trusted to have been written in order.
+  async fn get_resume_offset<P: AsRef<Path>>(source_file: &mut File, destination_file: P, source_file_size: u64, chunk_size: u64) -> u64 {
+    let destination_file_size =
+      match tokio::fs::metadata(destination_file.as_ref()).await {
+        Ok(meta) if meta.is_file() => meta.len(),
+        _ => return 0
+      };
+
+    if destination_file_size == 0 || destination_file_size >= source_file_size {
+      return 0
+    }
+
+    let Ok(mut destination) = File::open(destination_file.as_ref()).await else {
+      return 0
+    };
+
+    let chunk = min(chunk_size, destination_file_size);
+    let chunk_start = destination_file_size - chunk;
+
+    let source_chunk = Self::read_chunk(source_file, chunk_start, chunk).await;
+    let destination_chunk = Self::read_chunk(&mut destination, chunk_start, chunk).await;
 
-    match bytes_read_result {
-      Ok(value) => Ok(value as u64),
+    match (source_chunk, destination_chunk) {
+      (Some(source), Some(destination)) if source == destination => destination_file_size,
+      _ => 0
+    }
+  }
+
+  async fn read_chunk(file: &mut File, offset: u64, length: u64) -> Option<Vec<u8>> {
+    let mut buffer = vec![0; length as usize];
+    file.seek(SeekFrom::Start(offset)).await.ok()?;
+    file.read_exact(&mut buffer).await.ok()?;
+    Some(buffer)
+  }
+
+  async fn seek_source_file(file: &str, source_file: &mut File, offset: u64, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<()> {
+    match source_file.seek(SeekFrom::Start(offset)).await {
+      Ok(_) => Ok(()),
       Err(e) => {
         mux.send_read_failed(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
         Err(())
@@ -151,33 +802,68 @@ impl FileCopy {
     }
   }
 
-  async fn write_to_destination(file: &str, destination_file: &mut File, read_buffer: &[u8], mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<()> {
-    let bytes_written_result =
-      destination_file
-        .write(read_buffer)
+  /// Opens an existing destination file without truncating it and positions it at `offset`.
+  async fn open_destination_file_at(file_name: &str, destination_file: &Path, offset: u64, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<File> {
+    let result =
+      OpenOptions::new()
+        .write(true)
+        .open(destination_file)
         .await;
 
-    let bytes_written = match bytes_written_result {
-      Ok(value) => value as u64,
+    let opened =
+      match result {
+        Ok(mut df) => df.seek(SeekFrom::Start(offset)).await.map(|_| df),
+        Err(e) => Err(e)
+      };
+
+    match opened {
+      Ok(df) => {
+        mux.send_created_destination_file(progress_bar).await;
+        Ok(df)
+      },
       Err(e) => {
-        mux.send_write_to_destination_failed(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
-        return Err(())
+        mux.send_could_not_create_destination_file(file_name, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        Err(())
       }
-    };
+    }
+  }
+
+  async fn read_to_buffer(source_file: &mut File, buffer: &mut [u8]) -> Result<u64, TransferError> {
+    source_file
+      .read(buffer)
+      .await
+      .map(|bytes_read| bytes_read as u64)
+      .map_err(TransferError::Read)
+  }
 
-    mux.send_copy_in_progress(bytes_written, progress_bar).await;
+  async fn write_to_destination(destination_file: &mut File, read_buffer: &[u8], mux: &MonitorMux, progress_bar: &MyProgressBar) -> Result<(), TransferError> {
+    destination_file
+      .write_all(read_buffer)
+      .await
+      .map_err(TransferError::Write)?;
+
+    mux.send_copy_in_progress(read_buffer.len() as u64, CopyMethod::ReadWrite, progress_bar).await;
 
     Ok(())
   }
 
-  async fn complete_file_copy(file: &str, destination_file: &mut File, file_size: u64, mux: &MonitorMux, progress_bar: &MyProgressBar, file_name: &str) -> R<()> {
+  async fn complete_file_copy(file: &str, destination_file: &mut File, file_size: u64, sync: SyncMode, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<()> {
 
     mux.send_flushing_destination_file(progress_bar).await;
-    let flush_result = destination_file.flush().await;
 
-    match flush_result {
-      Ok(_) => (),
-      Err(e) => mux.send_flushing_to_destination_file_failed(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await,
+    if let Err(e) = destination_file.flush().await {
+      mux.send_flushing_to_destination_file_failed(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+      return Err(())
+    }
+
+    // Flushing only hands the data to the operating system, so sync it to disk before the file is renamed into place
+    if sync != SyncMode::None {
+      mux.send_syncing_file(progress_bar).await;
+
+      if let Err(e) = destination_file.sync_all().await {
+        mux.send_sync_failed(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        return Err(())
+      }
     }
 
     mux.send_copy_complete(progress_bar).await;
@@ -185,7 +871,6 @@ impl FileCopy {
     let dest_file_size = Self::get_destination_file_length(file, destination_file, mux, progress_bar).await?;
 
     Self::compare_file_sizes(file, file_size, dest_file_size, mux, progress_bar).await?;
-    Self::succeed(mux, progress_bar, file_name, file_size).await?;
 
     Ok(())
   }
@@ -193,10 +878,139 @@ impl FileCopy {
 
   async fn compare_file_sizes(file: &str, source_file_size: u64, destination_file_size: u64, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<()> {
     if source_file_size == destination_file_size {
-      mux.send_file_sizes_match(progress_bar).await
+      mux.send_file_sizes_match(progress_bar).await;
+      Ok(())
     } else {
       let size_comparison = SizeComparison::new(source_file_size, destination_file_size);
-      mux.send_files_sizes_are_different(file, size_comparison, progress_bar).await
+      mux.send_files_sizes_are_different(file, size_comparison, progress_bar).await;
+      Err(())
+    }
+  }
+
+  async fn verify_checksum(file: &str, source_hasher: Option<ChecksumHasher>, algorithm: ChecksumAlgorithm, source_file: &Path, destination_file: &Path, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<()> {
+    mux.send_verifying_checksum(progress_bar).await;
+
+    let source_checksum =
+      match source_hasher {
+        Some(hasher) => Ok(hasher.finalize()),
+        None => Self::calculate_checksum(source_file, algorithm).await
+      };
+
+    let destination_checksum = Self::calculate_checksum(destination_file, algorithm).await;
+
+    match (source_checksum, destination_checksum) {
+      (Ok(source), Ok(destination)) if source == destination => {
+        mux.send_checksums_match(&source, progress_bar).await;
+        Ok(())
+      },
+      (Ok(source), Ok(destination)) => {
+        mux.send_checksums_are_different(file, ChecksumComparison::new(&source, &destination), progress_bar).await;
+        Err(())
+      },
+      (Err(e), _) | (_, Err(e)) => {
+        mux.send_could_not_calculate_checksum(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        Err(())
+      }
+    }
+  }
+
+  async fn calculate_checksum(file: &Path, algorithm: ChecksumAlgorithm) -> std::io::Result<String> {
+    let file = file.to_path_buf();
+    tokio::task::spawn_blocking(move || file_checksum(file, algorithm))
+      .await
+      .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+  }
+
+  /// Extended attributes and ACLs are copied before the mode, as a read-only mode could prevent writing them.
+  /// Failures are only warnings, as the file content was copied successfully.
+  async fn copy_file_extended_attributes(file: &str, source_file: &Path, destination_file: &Path, options: &CopyOptions, mux: &MonitorMux, progress_bar: &MyProgressBar) {
+    let source_file = source_file.to_path_buf();
+    let destination_file = destination_file.to_path_buf();
+    let attributes = options.preserve().to_vec();
+    let namespaces = options.xattr_namespaces().to_vec();
+
+    let result =
+      tokio::task::spawn_blocking(move || copy_extended_attributes(source_file, destination_file, &attributes, &namespaces))
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+
+    if let Err(e) = result {
+      mux.send_could_not_copy_extended_attributes(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await
+    }
+  }
+
+  async fn preserve_file_attributes(file: &str, source_file: &Path, destination_file: &Path, attributes: &[PreserveAttribute], mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<()> {
+    mux.send_preserving_attributes(progress_bar).await;
+
+    let source_file = source_file.to_path_buf();
+    let destination_file = destination_file.to_path_buf();
+    let attributes = attributes.to_vec();
+
+    let result =
+      tokio::task::spawn_blocking(move || preserve_attributes(source_file, destination_file, &attributes))
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)));
+
+    match result {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        mux.send_could_not_preserve_attributes(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        Err(())
+      }
+    }
+  }
+
+  /// Keeps the destination file that is about to be replaced as its backup, if backups are enabled.
+  ///
+  /// This happens just before the partial file is renamed over the destination, so a failed copy leaves the destination alone,
+  /// and there is always a destination file while it's replaced.
+  async fn back_up_destination(&self, options: &CopyOptions, mux: &MonitorMux) -> R<()> {
+    let Some(backup) = options.backup() else {
+      return Ok(())
+    };
+
+    let progress_bar = &self.progress_bar;
+    let file_name = &self.source_file.relative_path();
+    mux.send_backing_up_destination(progress_bar).await;
+
+    match backup.back_up(&self.destination_file).await {
+      Ok(Some(backup_file)) => {
+        mux.send_backed_up_destination(file_name, backup_file, progress_bar).await;
+        Ok(())
+      },
+      Ok(None) => Ok(()),
+      Err(e) => {
+        mux.send_could_not_back_up_destination(file_name, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        Err(())
+      }
+    }
+  }
+
+  /// Moves a fully written and verified partial file to its destination, replacing any existing file.
+  async fn rename_partial_file(file: &str, partial_file: &Path, destination_file: &Path, mux: &MonitorMux, progress_bar: &MyProgressBar) -> R<()> {
+    mux.send_renaming_partial_file(progress_bar).await;
+
+    match tokio::fs::rename(partial_file, destination_file).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        mux.send_could_not_rename_partial_file(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        Err(())
+      }
+    }
+  }
+
+  /// Syncs the directory the destination was moved into, if asked to, so the rename itself is durable.
+  async fn sync_destination_directory(&self, options: &CopyOptions, mux: &MonitorMux) -> R<()> {
+    let progress_bar = &self.progress_bar;
+
+    if options.sync() == SyncMode::FileAndDir {
+      mux.send_syncing_directory(progress_bar).await;
+
+      let directory = self.destination_file.parent().unwrap_or(Path::new("."));
+      if let Err(e) = sync_directory(directory).await {
+        mux.send_sync_failed(&self.source_file.relative_path(), <std::io::Error as Into<CopyError>>::into(e), progress_bar).await;
+        return Err(())
+      }
     }
 
     Ok(())
@@ -207,3 +1021,280 @@ impl FileCopy {
     Ok(())
   }
 }
+
+/// How far a file's data copy has got, so it can continue after a retry.
+struct Transfer {
+  source_file: File,
+  destination_file: File,
+  /// Everything before this offset has been copied
+  position: u64,
+  /// The end of the data region being copied
+  data_end: u64,
+  copy_method: CopyMethod,
+  source_hasher: Option<ChecksumHasher>,
+  /// Set after a failure, as the file handles may no longer be usable
+  reopen: bool,
+}
+
+/// A failure while copying a file's data, which may be retried
+enum TransferError {
+  Read(std::io::Error),
+  Write(std::io::Error),
+}
+
+impl TransferError {
+
+  fn io_error(&self) -> &std::io::Error {
+    match self {
+      TransferError::Read(e) => e,
+      TransferError::Write(e) => e,
+    }
+  }
+
+  async fn send(self, file: &str, mux: &MonitorMux, progress_bar: &MyProgressBar) {
+    match self {
+      TransferError::Read(e) => mux.send_read_failed(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await,
+      TransferError::Write(e) => mux.send_write_to_destination_failed(file, <std::io::Error as Into<CopyError>>::into(e), progress_bar).await,
+    }
+  }
+}
+
+/// Splits a file into `count` ranges of about the same size, covering all of it.
+fn split_ranges(file_size: u64, count: u64) -> Vec<Range<u64>> {
+  let range_size = file_size.div_ceil(count).max(1);
+
+  (0..file_size)
+    .step_by(range_size as usize)
+    .map(|start| start..min(start + range_size, file_size))
+    .collect()
+}
+
+/// Copies `length` bytes at `offset` without moving either file's position, so ranges can share the files.
+#[cfg(unix)]
+fn copy_chunk_at(source: &std::fs::File, destination: &std::fs::File, offset: u64, length: u64) -> Result<u64, TransferError> {
+  use std::os::unix::fs::FileExt;
+
+  let mut buffer = vec![0; length as usize];
+  source.read_exact_at(&mut buffer, offset).map_err(TransferError::Read)?;
+  destination.write_all_at(&buffer, offset).map_err(TransferError::Write)?;
+
+  Ok(length)
+}
+
+#[cfg(not(unix))]
+fn copy_chunk_at(_source: &std::fs::File, _destination: &std::fs::File, _offset: u64, _length: u64) -> Result<u64, TransferError> {
+  Err(TransferError::Write(std::io::Error::new(std::io::ErrorKind::Unsupported, "Copying byte ranges is only supported on unix")))
+}
+
+#[cfg(unix)]
+async fn sync_directory(directory: &Path) -> std::io::Result<()> {
+  File::open(directory).await?.sync_all().await
+}
+
+/// Directories can only be opened, to sync them, on unix
+#[cfg(not(unix))]
+async fn sync_directory(_directory: &Path) -> std::io::Result<()> {
+  Ok(())
+}
+
+/// Whether both paths are on the same filesystem, so one can be renamed into the other
+#[cfg(unix)]
+async fn same_filesystem(a: &Path, b: &Path) -> bool {
+  use std::os::unix::fs::MetadataExt;
+
+  match (tokio::fs::metadata(a).await, tokio::fs::metadata(b).await) {
+    (Ok(a), Ok(b)) => a.dev() == b.dev(),
+    _ => false
+  }
+}
+
+/// Devices can't be compared, so leave it to the rename to fail
+#[cfg(not(unix))]
+async fn same_filesystem(_a: &Path, _b: &Path) -> bool {
+  true
+}
+
+#[cfg(unix)]
+pub(super) async fn symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+  tokio::fs::symlink(target, link).await
+}
+
+#[cfg(not(unix))]
+pub(super) async fn symlink(_target: &Path, _link: &Path) -> std::io::Result<()> {
+  Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Recreating symlinks is only supported on unix"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FailedReason, FileStatus};
+    use pretty_assertions::assert_eq;
+    use std::fs;
+
+    async fn resume_offset(source: &[u8], destination: &[u8], chunk_size: u64) -> u64 {
+      let dir = tempfile::tempdir().unwrap();
+      let source_path = dir.path().join("source");
+      let destination_path = dir.path().join("destination");
+      fs::write(&source_path, source).unwrap();
+      fs::write(&destination_path, destination).unwrap();
+
+      let mut source_file = File::open(&source_path).await.unwrap();
+      FileCopy::get_resume_offset(&mut source_file, &destination_path, source.len() as u64, chunk_size).await
+    }
+
+    #[tokio::test]
+    async fn resumes_from_end_of_matching_partial_file() {
+      let offset = resume_offset(b"0123456789", b"012345", 4).await;
+      assert_eq!(offset, 6)
+    }
+
+    #[tokio::test]
+    async fn does_not_resume_when_last_chunk_differs() {
+      let offset = resume_offset(b"0123456789", b"012355", 4).await;
+      assert_eq!(offset, 0)
+    }
+
+    #[tokio::test]
+    async fn does_not_resume_when_destination_is_larger_than_source() {
+      let offset = resume_offset(b"01234", b"012345", 4).await;
+      assert_eq!(offset, 0)
+    }
+
+    #[tokio::test]
+    async fn does_not_resume_when_destination_is_as_large_as_source() {
+      let offset = resume_offset(b"0123456789", b"xxxxxx6789", 4).await;
+      assert_eq!(offset, 0)
+    }
+
+    #[tokio::test]
+    async fn does_not_resume_when_destination_is_missing() {
+      let dir = tempfile::tempdir().unwrap();
+      let source_path = dir.path().join("source");
+      fs::write(&source_path, b"0123456789").unwrap();
+
+      let mut source_file = File::open(&source_path).await.unwrap();
+      let offset = FileCopy::get_resume_offset(&mut source_file, dir.path().join("missing"), 10, 4).await;
+      assert_eq!(offset, 0)
+    }
+
+    #[test]
+    fn splits_a_file_into_ranges_covering_all_of_it() {
+      assert_eq!(split_ranges(10, 3), vec![0..4, 4..8, 8..10])
+    }
+
+    #[test]
+    fn splits_a_small_file_into_fewer_ranges() {
+      assert_eq!(split_ranges(2, 4), vec![0..1, 1..2])
+    }
+
+    #[tokio::test]
+    async fn pipes_a_data_region_through_both_buffers() {
+      use crate::monitor::{InProgressSender, LifecycleEventSender, OverallProgressSender};
+      use indicatif::ProgressDrawTarget;
+
+      let dir = tempfile::tempdir().unwrap();
+      let data: Vec<u8> = (0..100).collect();
+      fs::write(dir.path().join("source"), &data).unwrap();
+
+      // Events are dropped, as nothing receives them
+      let mux =
+        MonitorMux::new(
+          InProgressSender::new(tokio::sync::mpsc::channel(1).0),
+          LifecycleEventSender::new(tokio::sync::mpsc::channel(1).0),
+          OverallProgressSender::new(tokio::sync::mpsc::channel(1).0)
+        );
+      let progress_bar = MyProgressBar::new(&MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+
+      let mut transfer =
+        Transfer {
+          source_file: File::open(dir.path().join("source")).await.unwrap(),
+          destination_file: File::create(dir.path().join("destination")).await.unwrap(),
+          position: 0,
+          data_end: 100,
+          copy_method: CopyMethod::ReadWrite,
+          source_hasher: Some(ChecksumHasher::new(ChecksumAlgorithm::Blake3)),
+          reopen: false,
+        };
+
+      let mut buffers = [vec![0; 7], vec![0; 7]];
+      let finished = FileCopy::pipe_data_region(&mut transfer, &mut buffers, &BandwidthLimiter::new(None), &mux, &progress_bar).await.ok();
+      transfer.destination_file.flush().await.unwrap();
+
+      assert_eq!(finished, Some(true));
+      assert_eq!(transfer.position, 100);
+      assert_eq!(transfer.source_hasher.map(|h| h.finalize()), Some(file_checksum(dir.path().join("source"), ChecksumAlgorithm::Blake3).unwrap()));
+      assert_eq!(fs::read(dir.path().join("destination")).unwrap(), data)
+    }
+
+    /// A mux that keeps the statuses sent to the lifecycle monitor, so they can be read back once it has been dropped.
+    fn collecting_mux() -> (MonitorMux, tokio::sync::mpsc::Receiver<FileStatus>) {
+      use crate::monitor::{InProgressSender, LifecycleEventSender, OverallProgressSender};
+
+      let (lifecycle_sender, lifecycle_receiver) = tokio::sync::mpsc::channel(1000);
+      let mux =
+        MonitorMux::new(
+          InProgressSender::new(tokio::sync::mpsc::channel(1000).0),
+          LifecycleEventSender::new(lifecycle_sender),
+          OverallProgressSender::new(tokio::sync::mpsc::channel(1000).0)
+        );
+
+      (mux, lifecycle_receiver)
+    }
+
+    async fn received_statuses(mut receiver: tokio::sync::mpsc::Receiver<FileStatus>) -> Vec<FileStatus> {
+      let mut statuses = vec![];
+      while let Some(status) = receiver.recv().await {
+        statuses.push(status)
+      }
+
+      statuses
+    }
+
+    fn finished_statuses(statuses: &[FileStatus]) -> Vec<&FileStatus> {
+      statuses
+        .iter()
+        .filter(|s| matches!(s, FileStatus::Success(..) | FileStatus::Skipped(..) | FileStatus::Failed(_)))
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn succeeds_once_for_a_copied_file() {
+      use clap::Parser;
+      use crate::args::{ExternalSymlinkPolicy, SymlinkMode};
+      use crate::cli::Args;
+      use crate::plan::{CopyPlan, PlanOptions};
+      use indicatif::ProgressDrawTarget;
+
+      let source = tempfile::tempdir().unwrap();
+      let destination = tempfile::tempdir().unwrap();
+      fs::write(source.path().join("file.txt"), [1; 100]).unwrap();
+
+      let args = Args::parse_from(["coop", "-s", &source.path().to_string_lossy(), "-d", &destination.path().to_string_lossy()]);
+      let source_files = SourceFile::get_source_files(&source.path().to_path_buf(), &[], SymlinkMode::Skip, ExternalSymlinkPolicy::Skip);
+      let entry = CopyPlan::new(source_files, destination.path(), &PlanOptions::from(&args)).into_entries().remove(0);
+      let file_copy = FileCopy::new(entry, &MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+
+      let (mux, receiver) = collecting_mux();
+      let result = file_copy.copy(CopyOptions::from(&args), mux).await;
+      let statuses = received_statuses(receiver).await;
+
+      assert_eq!(result, Ok(()));
+      assert!(matches!(finished_statuses(&statuses)[..], [FileStatus::Success(..)]));
+      assert!(matches!(statuses.last(), Some(FileStatus::Success(..))))
+    }
+
+    #[tokio::test]
+    async fn fails_when_the_sizes_are_different() {
+      use indicatif::ProgressDrawTarget;
+
+      let progress_bar = MyProgressBar::new(&MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+      let (mux, receiver) = collecting_mux();
+
+      let result = FileCopy::compare_file_sizes("file.txt", 100, 60, &mux, &progress_bar).await;
+      drop(mux);
+      let statuses = received_statuses(receiver).await;
+
+      assert_eq!(result, Err(()));
+      assert!(matches!(finished_statuses(&statuses)[..], [FileStatus::Failed(FailedReason::FileSizesAreDifferent(..))]))
+    }
+}
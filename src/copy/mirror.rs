@@ -0,0 +1,51 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::plan::ExtraneousFile;
+
+/// Removes a destination file that has no source file.
+///
+/// With a trash directory, the file is moved to the same relative path under it instead, and the path it
+/// was moved to is returned. Earlier files in the trash are kept, by giving the new file a numbered suffix.
+pub async fn remove_extraneous_file(file: &ExtraneousFile, trash_dir: Option<&Path>) -> io::Result<Option<PathBuf>> {
+  match trash_dir {
+    None => {
+      tokio::fs::remove_file(file.path()).await?;
+      Ok(None)
+    },
+    Some(trash_dir) => {
+      let trashed_file = unused_path(&trash_dir.join(file.relative_path())).await;
+
+      move_file(file.path(), &trashed_file).await?;
+      Ok(Some(trashed_file))
+    }
+  }
+}
+
+/// Moves a file, creating the directories it's moved into.
+pub async fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+  if let Some(parent) = to.parent() {
+    tokio::fs::create_dir_all(parent).await?;
+  }
+
+  // Renames don't work across filesystems, so fall back to copying the file
+  if tokio::fs::rename(from, to).await.is_err() {
+    tokio::fs::copy(from, to).await?;
+    tokio::fs::remove_file(from).await?;
+  }
+
+  Ok(())
+}
+
+/// The path itself if nothing is there, otherwise the first free "path.~N~"
+pub async fn unused_path(path: &Path) -> PathBuf {
+  let mut candidate = path.to_path_buf();
+  let mut suffix = 1;
+
+  while tokio::fs::symlink_metadata(&candidate).await.is_ok() {
+    candidate = PathBuf::from(format!("{}.~{suffix}~", path.to_string_lossy()));
+    suffix += 1;
+  }
+
+  candidate
+}
@@ -1,5 +1,23 @@
+mod accelerated;
+mod attributes;
+mod backup;
+mod bandwidth_limiter;
+mod checksum;
+mod copy_options;
 mod file_copy;
+mod mirror;
+mod retry;
 mod source_file;
+mod sparse;
 
+pub use accelerated::{copy_range, is_unsupported, reflink};
+pub use attributes::{copy_extended_attributes, preserve_attributes};
+pub use backup::BackupPolicy;
+pub use bandwidth_limiter::BandwidthLimiter;
+pub use checksum::{file_checksum, ChecksumHasher};
+pub use copy_options::CopyOptions;
 pub use file_copy::FileCopy;
-pub use source_file::SourceFile;
+pub use mirror::remove_extraneous_file;
+pub use retry::{is_transient, RetryPolicy};
+pub use source_file::{EntryType, HardLinkId, SourceFile};
+pub use sparse::next_data_region;
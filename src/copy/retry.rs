@@ -0,0 +1,88 @@
+use std::io;
+use std::time::Duration;
+
+use crate::args::RetryBackoff;
+
+/// The longest wait between two attempts, however many retries there have been
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// How often, and how patiently, to retry a file copy after a transient I/O error.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+  retries: u8,
+  backoff: RetryBackoff,
+}
+
+impl RetryPolicy {
+
+  pub fn new(retries: u8, backoff: RetryBackoff) -> Self {
+    Self {
+      retries,
+      backoff
+    }
+  }
+
+  pub fn retries(&self) -> u8 {
+    self.retries
+  }
+
+  /// How long to wait before `attempt`, starting at one. The backoff doubles with each attempt.
+  pub fn delay(&self, attempt: u8) -> Duration {
+    let factor = 2_u32.saturating_pow(attempt.saturating_sub(1) as u32);
+    self
+      .backoff
+      .duration()
+      .saturating_mul(factor)
+      .min(MAX_DELAY)
+  }
+}
+
+/// Errors that might go away if the same operation is tried again, such as those from a flaky network filesystem.
+pub fn is_transient(error: &io::Error) -> bool {
+  if matches!(error.kind(), io::ErrorKind::TimedOut | io::ErrorKind::Interrupted) {
+    return true
+  }
+
+  #[cfg(unix)]
+  {
+    matches!(error.raw_os_error(), Some(libc::EIO | libc::ETIMEDOUT | libc::ESTALE | libc::EAGAIN))
+  }
+
+  #[cfg(not(unix))]
+  {
+    false
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::str::FromStr;
+
+    #[test]
+    fn doubles_the_delay_for_each_attempt() {
+      let policy = RetryPolicy::new(5, RetryBackoff::from_str("500ms").unwrap());
+
+      assert_eq!(policy.delay(1), Duration::from_millis(500));
+      assert_eq!(policy.delay(2), Duration::from_secs(1));
+      assert_eq!(policy.delay(3), Duration::from_secs(2))
+    }
+
+    #[test]
+    fn caps_the_delay() {
+      let policy = RetryPolicy::new(255, RetryBackoff::from_str("10s").unwrap());
+
+      assert_eq!(policy.delay(255), MAX_DELAY)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_retries_transient_errors() {
+      assert!(is_transient(&io::Error::from_raw_os_error(libc::EIO)));
+      assert!(is_transient(&io::Error::from_raw_os_error(libc::ESTALE)));
+      assert!(is_transient(&io::Error::from_raw_os_error(libc::ETIMEDOUT)));
+      assert!(!is_transient(&io::Error::from_raw_os_error(libc::ENOSPC)));
+      assert!(!is_transient(&io::Error::from(io::ErrorKind::PermissionDenied)))
+    }
+}
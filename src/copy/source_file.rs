@@ -1,25 +1,48 @@
 use regex::Regex;
 use walkdir::{DirEntry, WalkDir};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+
+use crate::args::{ExternalSymlinkPolicy, SymlinkMode};
+use crate::model::SkipReason;
 
 #[derive(Debug, Clone)]
 pub struct SourceFile {
   full: PathBuf,
   relative: PathBuf,
   size: u64,
-  file_type: FileType
+  file_type: FileType,
+  entry_type: EntryType,
+  hard_link_id: Option<HardLinkId>,
+  /// Whether this is a symlink to a file, that is copied as the file it links to
+  followed_symlink: bool
 }
 
+/// Device and inode numbers of a file with more than one hard link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HardLinkId(u64, u64);
+
 #[derive(Debug, Clone)]
 enum FileType {
   File(u64),
   Dir
 }
 
+/// What was found at a source path
+#[derive(Debug, Clone)]
+pub enum EntryType {
+  File,
+  /// A directory to create at the destination, even if it's empty
+  Directory,
+  /// A symlink to recreate at the destination, with its target
+  Symlink(PathBuf),
+  /// An entry that won't be copied
+  Skipped(SkipReason)
+}
+
 impl SourceFile {
 
-  fn from_dir<P: AsRef<Path>>(source_dir: P, de: DirEntry, size: u64) -> Option<Self> {
+  fn from_dir<P: AsRef<Path>>(source_dir: P, de: DirEntry, size: u64, entry_type: EntryType) -> Option<Self> {
     let full = de.path().to_owned();
     de.
       into_path()
@@ -30,7 +53,10 @@ impl SourceFile {
           full,
           relative: rel.to_owned(),
           size,
-          file_type: FileType::Dir
+          file_type: FileType::Dir,
+          entry_type,
+          hard_link_id: None,
+          followed_symlink: false
         }
       })
   }
@@ -45,7 +71,10 @@ impl SourceFile {
       full,
       relative,
       size,
-      file_type
+      file_type,
+      entry_type: EntryType::File,
+      hard_link_id: None,
+      followed_symlink: false
     }
   }
 
@@ -71,7 +100,21 @@ impl SourceFile {
     self.size
   }
 
-  pub fn get_source_files(source_dir: &PathBuf, ignored_regexes: &[Regex]) -> Vec<SourceFile> {
+  pub fn entry_type(&self) -> &EntryType {
+    &self.entry_type
+  }
+
+  /// Set when this file is one of several hard links to the same data
+  pub fn hard_link_id(&self) -> Option<HardLinkId> {
+    self.hard_link_id
+  }
+
+  /// Set when this is a symlink that is copied as the file it links to, with --symlinks follow
+  pub fn is_followed_symlink(&self) -> bool {
+    self.followed_symlink
+  }
+
+  pub fn get_source_files(source_dir: &PathBuf, ignored_regexes: &[Regex], symlinks: SymlinkMode, external_symlinks: ExternalSymlinkPolicy) -> Vec<SourceFile> {
     let file_type =
       fs::File::open(source_dir)
         .and_then(|f| f.metadata() )
@@ -86,40 +129,210 @@ impl SourceFile {
 
     match file_type {
       FileType::File(size) => Self::get_file(source_dir, size),
-      FileType::Dir => Self::get_directory_files(source_dir, ignored_regexes),
+      FileType::Dir => Self::get_directory_files(source_dir, ignored_regexes, symlinks, external_symlinks),
     }
   }
 
-  fn ignored(ignored_regexes: &[Regex], de: &DirEntry) -> bool {
+  fn ignored(ignored_regexes: &[Regex], path: &Path) -> bool {
     ignored_regexes
       .iter()
-      .any(|r| r.is_match(de.path().to_string_lossy().as_ref()))
+      .any(|r| r.is_match(path.to_string_lossy().as_ref()))
   }
 
   fn get_file(source_file: &PathBuf, size: u64) -> Vec<SourceFile> {
     vec![SourceFile::from_file(source_file, size)]
   }
 
-  fn get_directory_files(source_dir: &PathBuf, ignored_regexes: &[Regex]) -> Vec<SourceFile> {
-    WalkDir::new(source_dir)
-      .into_iter()
-      .filter_map(|de| {
-        de
-          .ok()
-          .filter(|d| {
-            // We only want files and not directories or symlinks
-            // We might want to filter out certain files like .DS_Store
-            d.file_type().is_file() && !Self::ignored(ignored_regexes, d)
-          })
-          .and_then(|file| {
-            file
-              .metadata()
-              .ok()
-              .and_then(|meta| {
-                SourceFile::from_dir(source_dir, file, meta.len())
-              })
-          })
-      })
-      .collect()
+  fn get_directory_files(source_dir: &PathBuf, ignored_regexes: &[Regex], symlinks: SymlinkMode, external_symlinks: ExternalSymlinkPolicy) -> Vec<SourceFile> {
+    let follow_links = symlinks == SymlinkMode::Follow;
+    let mut source_files = vec![];
+    let mut entries = WalkDir::new(source_dir).follow_links(follow_links).into_iter();
+
+    while let Some(entry) = entries.next() {
+      let de =
+        match entry {
+          Ok(de) => de,
+          Err(e) => {
+            // Anything that can't be walked is listed as skipped, instead of silently left out
+            if let Some(path) = e.path().filter(|_| e.depth() > 0) {
+              if !Self::ignored(ignored_regexes, path) {
+                source_files.push(Self::skipped_walk_error(source_dir, path, &e))
+              }
+            }
+            continue
+          }
+        };
+
+      // We might want to filter out certain files like .DS_Store
+      if Self::ignored(ignored_regexes, de.path()) {
+        continue
+      }
+
+      if de.path_is_symlink() && de.depth() > 0 {
+        let external = Self::is_external_symlink(source_dir, de.path());
+
+        if external && external_symlinks == ExternalSymlinkPolicy::Skip {
+          // When following links, don't walk into directories outside the source
+          if de.file_type().is_dir() {
+            entries.skip_current_dir()
+          }
+
+          let target = fs::read_link(de.path()).unwrap_or_default();
+          source_files.extend(SourceFile::from_dir(source_dir, de, 0, EntryType::Skipped(SkipReason::ExternalSymlink(target))));
+          continue
+        }
+
+        match symlinks {
+          SymlinkMode::Recreate => {
+            if let Ok(target) = fs::read_link(de.path()) {
+              source_files.extend(SourceFile::from_dir(source_dir, de, 0, EntryType::Symlink(target)))
+            }
+            continue
+          },
+          SymlinkMode::Skip => {
+            let target = fs::read_link(de.path()).unwrap_or_default();
+            source_files.extend(SourceFile::from_dir(source_dir, de, 0, EntryType::Skipped(SkipReason::Symlink(target))));
+            continue
+          },
+          SymlinkMode::Follow => ()
+        }
+      }
+
+      // The source directory itself is the destination directory
+      if de.file_type().is_dir() {
+        if de.depth() > 0 {
+          source_files.extend(SourceFile::from_dir(source_dir, de, 0, EntryType::Directory))
+        }
+      } else if de.file_type().is_file() {
+        let followed_symlink = de.path_is_symlink();
+        let source_file =
+          de
+            .metadata()
+            .ok()
+            .and_then(|meta| {
+              let hard_link_id = hard_link_id(&meta);
+              SourceFile::from_dir(source_dir, de, meta.len(), EntryType::File)
+                .map(|sf| Self { hard_link_id, followed_symlink, ..sf })
+            });
+
+        if let Some(source_file) = source_file {
+          source_files.push(source_file)
+        }
+      }
+    }
+
+    source_files
+  }
+
+  fn skipped_walk_error(source_dir: &Path, path: &Path, error: &walkdir::Error) -> SourceFile {
+    let is_symlink = fs::symlink_metadata(path).is_ok_and(|meta| meta.is_symlink());
+    let target = || fs::read_link(path).unwrap_or_default();
+
+    // Walkdir detects symlinks that point to one of their ancestors when following links
+    let reason =
+      if error.loop_ancestor().is_some() {
+        SkipReason::SymlinkLoop(target())
+      } else if is_symlink && fs::metadata(path).is_err() {
+        SkipReason::BrokenSymlink(target())
+      } else {
+        SkipReason::Unreadable(error.io_error().map(ToString::to_string).unwrap_or_else(|| error.to_string()))
+      };
+
+    Self {
+      full: path.to_owned(),
+      relative: path.strip_prefix(source_dir).unwrap_or(path).to_owned(),
+      size: 0,
+      file_type: FileType::Dir,
+      entry_type: EntryType::Skipped(reason),
+      hard_link_id: None,
+      followed_symlink: false
+    }
+  }
+
+  /// Whether the symlink at `link` resolves to somewhere outside `source_dir`.
+  fn is_external_symlink(source_dir: &Path, link: &Path) -> bool {
+    // Compare fully resolved paths when the target exists, so links between symlinked directories are handled
+    if let (Ok(source_dir), Ok(resolved)) = (fs::canonicalize(source_dir), fs::canonicalize(link)) {
+      return !resolved.starts_with(source_dir)
+    }
+
+    // Dangling links are resolved against the directory they are in
+    match (std::path::absolute(source_dir), std::path::absolute(link), fs::read_link(link)) {
+      (Ok(source_dir), Ok(link), Ok(target)) => {
+        let link_dir = link.parent().unwrap_or(&link);
+        !normalise(&link_dir.join(target)).starts_with(normalise(&source_dir))
+      },
+      _ => true
+    }
   }
 }
+
+#[cfg(unix)]
+fn hard_link_id(meta: &fs::Metadata) -> Option<HardLinkId> {
+  use std::os::unix::fs::MetadataExt;
+  (meta.nlink() > 1).then(|| HardLinkId(meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn hard_link_id(_meta: &fs::Metadata) -> Option<HardLinkId> {
+  None
+}
+
+/// Removes `.` and `..` components from a path without touching the filesystem.
+fn normalise(path: &Path) -> PathBuf {
+  let mut normalised = PathBuf::new();
+
+  for component in path.components() {
+    match component {
+      Component::CurDir => (),
+      Component::ParentDir => {
+        normalised.pop();
+      },
+      other => normalised.push(other)
+    }
+  }
+
+  normalised
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[cfg(unix)]
+    #[test]
+    fn skips_broken_symlinks_when_following_them() {
+      let source = tempfile::tempdir().unwrap();
+      std::fs::write(source.path().join("file.txt"), "content").unwrap();
+      std::os::unix::fs::symlink("missing.txt", source.path().join("broken.txt")).unwrap();
+
+      let mut source_files: Vec<(String, String)> =
+        SourceFile::get_source_files(&source.path().to_path_buf(), &[], SymlinkMode::Follow, ExternalSymlinkPolicy::Allow)
+          .into_iter()
+          .map(|f| {
+            let entry_type = format!("{:?}", f.entry_type());
+            (f.relative_path(), entry_type)
+          })
+          .collect();
+      source_files.sort();
+
+      assert_eq!(
+        source_files,
+        vec![
+          ("broken.txt".to_owned(), r#"Skipped(BrokenSymlink("missing.txt"))"#.to_owned()),
+          ("file.txt".to_owned(), "File".to_owned())
+        ]
+      )
+    }
+
+    #[test]
+    fn normalises_parent_and_current_components() {
+      assert_eq!(normalise(Path::new("/source/a/./../b/c")), PathBuf::from("/source/b/c"))
+    }
+
+    #[test]
+    fn normalises_beyond_root_to_root() {
+      assert_eq!(normalise(Path::new("/source/../../etc")), PathBuf::from("/etc"))
+    }
+}
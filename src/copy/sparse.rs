@@ -0,0 +1,84 @@
+use std::ops::Range;
+
+/// Finds the next region of data in a file at or after `offset`, using SEEK_DATA and SEEK_HOLE.
+///
+/// Everything between `offset` and the start of the region is a hole. `None` means only a hole
+/// remains before `file_size`. Filesystems that can't report holes have a single data region
+/// covering the whole file.
+///
+/// This moves the file's position, so callers need to seek afterwards.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+pub fn next_data_region<F: std::os::fd::AsRawFd>(file: &F, offset: u64, file_size: u64) -> Option<Range<u64>> {
+  if offset >= file_size {
+    return None
+  }
+
+  let fd = file.as_raw_fd();
+  let data_start =
+    match lseek(fd, offset, libc::SEEK_DATA) {
+      Ok(start) => start,
+      // There is no data after offset
+      Err(e) if e.raw_os_error() == Some(libc::ENXIO) => return None,
+      Err(_) => return Some(offset..file_size),
+    };
+
+  if data_start >= file_size {
+    return None
+  }
+
+  let data_end = lseek(fd, data_start, libc::SEEK_HOLE).unwrap_or(file_size);
+  Some(data_start..data_end.min(file_size))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd")))]
+pub fn next_data_region<F>(_file: &F, offset: u64, file_size: u64) -> Option<Range<u64>> {
+  (offset < file_size).then_some(offset..file_size)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+fn lseek(fd: std::os::fd::RawFd, offset: u64, whence: libc::c_int) -> std::io::Result<u64> {
+  // SAFETY: lseek only reads its arguments and the file descriptor is owned by the caller's file
+  let result = unsafe { libc::lseek(fd, offset as libc::off_t, whence) };
+  if result < 0 {
+    Err(std::io::Error::last_os_error())
+  } else {
+    Ok(result as u64)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::{Seek, SeekFrom, Write};
+
+    #[test]
+    fn whole_file_is_data_when_there_are_no_holes() {
+      let mut file = tempfile::tempfile().unwrap();
+      file.write_all(&[1; 1000]).unwrap();
+
+      assert_eq!(next_data_region(&file, 0, 1000), Some(0..1000));
+      assert_eq!(next_data_region(&file, 1000, 1000), None)
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finds_data_after_a_hole() {
+      let hole = 1024 * 1024;
+      let mut file = tempfile::tempfile().unwrap();
+      file.seek(SeekFrom::Start(hole)).unwrap();
+      file.write_all(&[1; 10]).unwrap();
+      file.set_len(hole * 3).unwrap();
+
+      // Filesystems that don't support holes report everything as data
+      match next_data_region(&file, 0, hole * 3) {
+        Some(region) if region.start == 0 => assert_eq!(region, 0..hole * 3),
+        Some(region) => {
+          assert_eq!(region.start, hole);
+          assert!(region.end > hole && region.end < hole * 3, "{region:?}");
+          assert_eq!(next_data_region(&file, region.end, hole * 3), None)
+        },
+        None => panic!("no data found")
+      }
+    }
+}
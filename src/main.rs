@@ -1,3 +1,5 @@
+use std::process::ExitCode;
+
 use args::cli;
 use workflow::CoopWorkflow;
 
@@ -8,10 +10,12 @@ mod monitor;
 mod copy;
 mod workflow;
 mod console;
+mod plan;
+mod report;
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
   let args = cli::get_cli_args();
   let workflow = CoopWorkflow::new(args);
-  workflow.run().await
+  workflow.run().await.into()
 }
@@ -1,5 +1,5 @@
 use std::fmt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use crate::{model::size_pretty, progress::MyProgressBar};
 
 #[derive(Debug, Clone)]
@@ -10,11 +10,37 @@ pub enum FileStatus {
   GettingDestinationFileLength(MyProgressBar),
   CreatedDestinationFile(MyProgressBar),
   InProgress(u64),
+  Resumed(u64, MyProgressBar),
+  SkippedHole(u64, MyProgressBar),
+  Retrying(FileName, RetryAttempt, CopyError, MyProgressBar),
   CopyComplete(Complete),
   FileSizesMatch(MyProgressBar),
+  VerifyingChecksum(MyProgressBar),
+  /// The checksum both files have
+  ChecksumsMatch(String, MyProgressBar),
+  PreservingAttributes(MyProgressBar),
+  RenamingPartialFile(MyProgressBar),
+  CreatedSymlink(MyProgressBar),
+  CreatedHardLink(MyProgressBar),
+  MovedByRename(u64, MyProgressBar),
+  DeletedSource(MyProgressBar),
+  BackingUpDestination(MyProgressBar),
+  /// The destination file that is about to be replaced was moved to this backup path
+  BackedUpDestination(FileName, PathBuf, MyProgressBar),
+  /// A destination directory was created for a source directory
+  CreatedDirectory(FileName),
+  CouldNotCreateDirectory(FileName, CopyError),
+  /// A destination file without a source file was deleted, or moved to this trash path
+  RemovedExtraneous(FileName, Option<PathBuf>),
+  CouldNotRemoveExtraneous(FileName, CopyError),
   Success(FileName, FileSize, MyProgressBar),
+  Skipped(FileName, FileSize, SkipReason, MyProgressBar),
   Failed(FailedReason),
-  Flushing(MyProgressBar)
+  /// A problem that doesn't stop the file from being copied
+  Warning(FailedReason),
+  Flushing(MyProgressBar),
+  SyncingFile(MyProgressBar),
+  SyncingDirectory(MyProgressBar),
 }
 
 #[derive(Debug, Clone)]
@@ -57,10 +83,47 @@ impl fmt::Display for FileSize {
 }
 
 
+/// Why a source file was not copied
+#[derive(Debug, Clone)]
+pub enum SkipReason {
+  SameSizeAndModifiedTime,
+  SameChecksum,
+  Symlink(PathBuf),
+  ExternalSymlink(PathBuf),
+  SymlinkLoop(PathBuf),
+  /// A symlink to something that doesn't exist, when following symlinks
+  BrokenSymlink(PathBuf),
+  /// Couldn't be read while finding the files to copy, with why
+  Unreadable(String),
+  /// Kept by --overwrite never
+  DestinationExists,
+  /// Kept by --overwrite if-newer
+  DestinationNotOlder,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      let reason = match self {
+        SkipReason::SameSizeAndModifiedTime => "up to date (same size and modified time)".to_owned(),
+        SkipReason::SameChecksum => "up to date (same checksum)".to_owned(),
+        SkipReason::Symlink(target) => format!("symlink to {}", target.to_string_lossy()),
+        SkipReason::ExternalSymlink(target) => format!("symlink to {} points outside the source", target.to_string_lossy()),
+        SkipReason::SymlinkLoop(target) => format!("symlink to {} loops back to a parent directory", target.to_string_lossy()),
+        SkipReason::BrokenSymlink(target) => format!("symlink to {} is broken", target.to_string_lossy()),
+        SkipReason::Unreadable(error) => format!("could not be read: {error}"),
+        SkipReason::DestinationExists => "destination already exists".to_owned(),
+        SkipReason::DestinationNotOlder => "destination is not older than the source".to_owned(),
+      };
+
+      write!(f, "{}", reason)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CopyError(String);
 
 impl CopyError {
+  #[cfg(test)]
   pub fn new(message: &str) -> Self {
     Self(message.to_owned())
   }
@@ -85,14 +148,16 @@ impl <T> From<T> for CopyError where
 #[derive(Debug, Clone)]
 pub struct InProgress {
   bytes_written: u64,
+  copy_method: CopyMethod,
   progress_bar: MyProgressBar
 }
 
 impl InProgress {
 
-  pub fn new(bytes_written: u64, progress_bar: &MyProgressBar) -> Self {
+  pub fn new(bytes_written: u64, copy_method: CopyMethod, progress_bar: &MyProgressBar) -> Self {
     Self {
       bytes_written,
+      copy_method,
       progress_bar: progress_bar.clone()
     }
   }
@@ -104,6 +169,64 @@ impl InProgress {
   pub fn bytes_written(&self) -> u64 {
     self.bytes_written
   }
+
+  pub fn copy_method(&self) -> CopyMethod {
+    self.copy_method
+  }
+}
+
+/// Which retry of how many is being attempted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryAttempt {
+  attempt: u8,
+  retries: u8,
+}
+
+impl RetryAttempt {
+  pub fn new(attempt: u8, retries: u8) -> Self {
+    Self {
+      attempt,
+      retries
+    }
+  }
+
+  pub fn attempt(&self) -> u8 {
+    self.attempt
+  }
+
+  pub fn retries(&self) -> u8 {
+    self.retries
+  }
+}
+
+impl fmt::Display for RetryAttempt {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}/{}", self.attempt, self.retries)
+  }
+}
+
+/// How the data of a file is being copied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyMethod {
+  /// Extents shared with a FICLONE reflink
+  Reflink,
+  /// Copied within the kernel with copy_file_range
+  CopyFileRange,
+  /// Read into a buffer and written out again
+  ReadWrite,
+  /// Several byte ranges copied at once with positional reads and writes
+  ParallelRanges,
+}
+
+impl fmt::Display for CopyMethod {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      CopyMethod::Reflink => write!(f, "reflink"),
+      CopyMethod::CopyFileRange => write!(f, "copy_file_range"),
+      CopyMethod::ReadWrite => write!(f, "read/write"),
+      CopyMethod::ParallelRanges => write!(f, "parallel ranges"),
+    }
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -154,14 +277,102 @@ impl SizeComparison {
   }
 }
 
+#[derive(Debug, Clone)]
+pub struct ChecksumComparison {
+  source: String,
+  destination: String
+}
+
+impl ChecksumComparison {
+
+  pub fn new(source: &str, destination: &str) -> Self {
+    Self {
+      source: source.to_owned(),
+      destination: destination.to_owned(),
+    }
+  }
+
+  pub fn source_checksum(&self) -> &str {
+    &self.source
+  }
+
+  pub fn destination_checksum(&self) -> &str {
+    &self.destination
+  }
+}
+
 #[derive(Debug, Clone)]
 pub enum FailedReason {
   ReadFailed(FileName, CopyError, MyProgressBar),
   WriteFailed(FileName, CopyError, MyProgressBar),
   FlushFailed(FileName, CopyError, MyProgressBar),
+  SyncFailed(FileName, CopyError, MyProgressBar),
   CouldNotReadSourceFile(FileName, CopyError, MyProgressBar),
   CouldNotGetDestinationFileSize(FileName, CopyError, MyProgressBar),
   CouldNotCreateDestinationFile(FileName, CopyError, MyProgressBar),
   CouldNotCreateDestinationDir(FileName, CopyError, MyProgressBar),
   FileSizesAreDifferent(FileName, SizeComparison, MyProgressBar),
+  CouldNotCalculateChecksum(FileName, CopyError, MyProgressBar),
+  ChecksumsAreDifferent(FileName, ChecksumComparison, MyProgressBar),
+  CouldNotPreserveAttributes(FileName, CopyError, MyProgressBar),
+  CouldNotCopyExtendedAttributes(FileName, CopyError, MyProgressBar),
+  CouldNotRenamePartialFile(FileName, CopyError, MyProgressBar),
+  CouldNotCreateSymlink(FileName, CopyError, MyProgressBar),
+  CouldNotCreateHardLink(FileName, CopyError, MyProgressBar),
+  CouldNotDeleteSource(FileName, CopyError, MyProgressBar),
+  CouldNotBackUpDestination(FileName, CopyError, MyProgressBar),
+}
+
+impl FailedReason {
+  pub fn file_name(&self) -> &FileName {
+    match self {
+      FailedReason::ReadFailed(file_name, _, _) |
+      FailedReason::WriteFailed(file_name, _, _) |
+      FailedReason::FlushFailed(file_name, _, _) |
+      FailedReason::SyncFailed(file_name, _, _) |
+      FailedReason::CouldNotReadSourceFile(file_name, _, _) |
+      FailedReason::CouldNotGetDestinationFileSize(file_name, _, _) |
+      FailedReason::CouldNotCreateDestinationFile(file_name, _, _) |
+      FailedReason::CouldNotCreateDestinationDir(file_name, _, _) |
+      FailedReason::FileSizesAreDifferent(file_name, _, _) |
+      FailedReason::CouldNotCalculateChecksum(file_name, _, _) |
+      FailedReason::ChecksumsAreDifferent(file_name, _, _) |
+      FailedReason::CouldNotPreserveAttributes(file_name, _, _) |
+      FailedReason::CouldNotCopyExtendedAttributes(file_name, _, _) |
+      FailedReason::CouldNotRenamePartialFile(file_name, _, _) |
+      FailedReason::CouldNotCreateSymlink(file_name, _, _) |
+      FailedReason::CouldNotCreateHardLink(file_name, _, _) |
+      FailedReason::CouldNotDeleteSource(file_name, _, _) |
+      FailedReason::CouldNotBackUpDestination(file_name, _, _) => file_name
+    }
+  }
+}
+
+impl fmt::Display for FailedReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      let reason = match self {
+        FailedReason::ReadFailed(_, error, _) => format!("Read failed: {}", error.0),
+        FailedReason::WriteFailed(_, error, _) => format!("Write failed: {}", error.0),
+        FailedReason::FlushFailed(_, error, _) => format!("Flushing destination file failed: {}", error.0),
+        FailedReason::SyncFailed(_, error, _) => format!("Could not sync to disk: {}", error.0),
+        FailedReason::CouldNotReadSourceFile(_, error, _) => format!("Could not read source file: {}", error.0),
+        FailedReason::CouldNotGetDestinationFileSize(_, error, _) => format!("Could not get destination file size: {}", error.0),
+        FailedReason::CouldNotCreateDestinationFile(_, error, _) => format!("Could not create destination file: {}", error.0),
+        FailedReason::CouldNotCreateDestinationDir(_, error, _) => format!("Could not create destination dir: {}", error.0),
+        FailedReason::FileSizesAreDifferent(_, comparison, _) =>
+          format!("File sizes are different. src:{}, dst:{}", comparison.source_size(), comparison.destination_size()),
+        FailedReason::CouldNotCalculateChecksum(_, error, _) => format!("Could not calculate checksum: {}", error.0),
+        FailedReason::ChecksumsAreDifferent(_, comparison, _) =>
+          format!("Checksums are different. src:{}, dst:{}", comparison.source_checksum(), comparison.destination_checksum()),
+        FailedReason::CouldNotPreserveAttributes(_, error, _) => format!("Could not preserve attributes: {}", error.0),
+        FailedReason::CouldNotCopyExtendedAttributes(_, error, _) => format!("Could not copy extended attributes: {}", error.0),
+        FailedReason::CouldNotRenamePartialFile(_, error, _) => format!("Could not move partial file into place: {}", error.0),
+        FailedReason::CouldNotCreateSymlink(_, error, _) => format!("Could not create symlink: {}", error.0),
+        FailedReason::CouldNotCreateHardLink(_, error, _) => format!("Could not create hard link: {}", error.0),
+        FailedReason::CouldNotDeleteSource(_, error, _) => format!("Could not delete source: {}", error.0),
+        FailedReason::CouldNotBackUpDestination(_, error, _) => format!("Could not back up destination: {}", error.0),
+      };
+
+      write!(f, "{}", reason)
+    }
 }
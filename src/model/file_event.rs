@@ -0,0 +1,198 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use super::{FailedReason, FileStatus};
+
+/// A status reported for a file, in a form that can be written out as JSON.
+#[derive(Debug, Clone, Serialize)]
+pub struct FileEvent {
+  timestamp: DateTime<Local>,
+  /// Relative to the source directory
+  path: String,
+  #[serde(flatten)]
+  kind: FileEventKind,
+}
+
+/// What happened, without the progress bar that `FileStatus` carries for display.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum FileEventKind {
+  NotStarted,
+  OpenedSourceFile,
+  GettingDestinationFileLength,
+  GotDestinationFileLength,
+  CreatedDestinationFile,
+  /// `bytes` have been copied since the last progress event, and `copied` since the file started
+  Progress { bytes: u64, copied: u64 },
+  Resumed { offset: u64 },
+  SkippedHole { bytes: u64 },
+  Retrying { attempt: u8, retries: u8, error: String },
+  CopyComplete,
+  Flushing,
+  SyncingFile,
+  SyncingDirectory,
+  FileSizesMatch,
+  VerifyingChecksum,
+  ChecksumsMatch { checksum: String },
+  PreservingAttributes,
+  RenamingPartialFile,
+  CreatedSymlink,
+  CreatedHardLink,
+  MovedByRename { bytes: u64 },
+  DeletedSource,
+  BackingUpDestination,
+  BackedUpDestination { backup: String },
+  CreatedDirectory,
+  CouldNotCreateDirectory { error: String },
+  RemovedExtraneous { trashed_to: Option<String> },
+  CouldNotRemoveExtraneous { error: String },
+  Success { bytes: u64 },
+  Skipped { bytes: u64, reason: String },
+  Failed { reason: &'static str, error: String },
+  Warning { reason: &'static str, error: String },
+}
+
+impl FileEvent {
+  pub fn new(path: &str, kind: FileEventKind) -> Self {
+    Self::at(path, kind, Local::now())
+  }
+
+  pub fn at(path: &str, kind: FileEventKind, timestamp: DateTime<Local>) -> Self {
+    Self {
+      timestamp,
+      path: path.to_owned(),
+      kind
+    }
+  }
+
+  pub fn timestamp(&self) -> DateTime<Local> {
+    self.timestamp
+  }
+
+  pub fn path(&self) -> &str {
+    &self.path
+  }
+
+  pub fn kind(&self) -> &FileEventKind {
+    &self.kind
+  }
+}
+
+impl FileEventKind {
+
+  /// The event for a status. Progress is left out, as it's aggregated before it becomes an event.
+  pub fn from_status(status: &FileStatus) -> Option<Self> {
+    let kind =
+      match status {
+        FileStatus::NotStarted(_) => FileEventKind::NotStarted,
+        FileStatus::OpenedSourceFile(_) => FileEventKind::OpenedSourceFile,
+        FileStatus::GettingDestinationFileLength(_) => FileEventKind::GettingDestinationFileLength,
+        FileStatus::GotDestinationFileLength(_) => FileEventKind::GotDestinationFileLength,
+        FileStatus::CreatedDestinationFile(_) => FileEventKind::CreatedDestinationFile,
+        FileStatus::InProgress(_) => return None,
+        FileStatus::Resumed(offset, _) => FileEventKind::Resumed { offset: *offset },
+        FileStatus::SkippedHole(bytes, _) => FileEventKind::SkippedHole { bytes: *bytes },
+        FileStatus::Retrying(_, attempt, error, _) =>
+          FileEventKind::Retrying { attempt: attempt.attempt(), retries: attempt.retries(), error: error.clone().error() },
+        FileStatus::CopyComplete(_) => FileEventKind::CopyComplete,
+        FileStatus::Flushing(_) => FileEventKind::Flushing,
+        FileStatus::SyncingFile(_) => FileEventKind::SyncingFile,
+        FileStatus::SyncingDirectory(_) => FileEventKind::SyncingDirectory,
+        FileStatus::FileSizesMatch(_) => FileEventKind::FileSizesMatch,
+        FileStatus::VerifyingChecksum(_) => FileEventKind::VerifyingChecksum,
+        FileStatus::ChecksumsMatch(checksum, _) => FileEventKind::ChecksumsMatch { checksum: checksum.clone() },
+        FileStatus::PreservingAttributes(_) => FileEventKind::PreservingAttributes,
+        FileStatus::RenamingPartialFile(_) => FileEventKind::RenamingPartialFile,
+        FileStatus::CreatedSymlink(_) => FileEventKind::CreatedSymlink,
+        FileStatus::CreatedHardLink(_) => FileEventKind::CreatedHardLink,
+        FileStatus::MovedByRename(bytes, _) => FileEventKind::MovedByRename { bytes: *bytes },
+        FileStatus::DeletedSource(_) => FileEventKind::DeletedSource,
+        FileStatus::BackingUpDestination(_) => FileEventKind::BackingUpDestination,
+        FileStatus::BackedUpDestination(_, backup, _) => FileEventKind::BackedUpDestination { backup: backup.to_string_lossy().into_owned() },
+        FileStatus::CreatedDirectory(_) => FileEventKind::CreatedDirectory,
+        FileStatus::CouldNotCreateDirectory(_, error) => FileEventKind::CouldNotCreateDirectory { error: error.clone().error() },
+        FileStatus::RemovedExtraneous(_, trashed_to) =>
+          FileEventKind::RemovedExtraneous { trashed_to: trashed_to.as_ref().map(|p| p.to_string_lossy().into_owned()) },
+        FileStatus::CouldNotRemoveExtraneous(_, error) => FileEventKind::CouldNotRemoveExtraneous { error: error.clone().error() },
+        FileStatus::Success(_, size, _) => FileEventKind::Success { bytes: size.clone().size() },
+        FileStatus::Skipped(_, size, reason, _) => FileEventKind::Skipped { bytes: size.clone().size(), reason: reason.to_string() },
+        FileStatus::Failed(reason) => FileEventKind::Failed { reason: failure_kind(reason), error: reason.to_string() },
+        FileStatus::Warning(reason) => FileEventKind::Warning { reason: failure_kind(reason), error: reason.to_string() },
+      };
+
+    Some(kind)
+  }
+}
+
+/// A stable name for each kind of failure, for scripts to match on
+fn failure_kind(reason: &FailedReason) -> &'static str {
+  match reason {
+    FailedReason::ReadFailed(..) => "read_failed",
+    FailedReason::WriteFailed(..) => "write_failed",
+    FailedReason::FlushFailed(..) => "flush_failed",
+    FailedReason::SyncFailed(..) => "sync_failed",
+    FailedReason::CouldNotReadSourceFile(..) => "could_not_read_source_file",
+    FailedReason::CouldNotGetDestinationFileSize(..) => "could_not_get_destination_file_size",
+    FailedReason::CouldNotCreateDestinationFile(..) => "could_not_create_destination_file",
+    FailedReason::CouldNotCreateDestinationDir(..) => "could_not_create_destination_dir",
+    FailedReason::FileSizesAreDifferent(..) => "file_sizes_are_different",
+    FailedReason::CouldNotCalculateChecksum(..) => "could_not_calculate_checksum",
+    FailedReason::ChecksumsAreDifferent(..) => "checksums_are_different",
+    FailedReason::CouldNotPreserveAttributes(..) => "could_not_preserve_attributes",
+    FailedReason::CouldNotCopyExtendedAttributes(..) => "could_not_copy_extended_attributes",
+    FailedReason::CouldNotRenamePartialFile(..) => "could_not_rename_partial_file",
+    FailedReason::CouldNotCreateSymlink(..) => "could_not_create_symlink",
+    FailedReason::CouldNotCreateHardLink(..) => "could_not_create_hard_link",
+    FailedReason::CouldNotDeleteSource(..) => "could_not_delete_source",
+    FailedReason::CouldNotBackUpDestination(..) => "could_not_back_up_destination",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{CopyError, FileName, FileSize, SkipReason};
+    use crate::progress::MyProgressBar;
+    use indicatif::{MultiProgress, ProgressDrawTarget};
+    use pretty_assertions::assert_eq;
+
+    fn progress_bar() -> MyProgressBar {
+      MyProgressBar::new(&MultiProgress::with_draw_target(ProgressDrawTarget::hidden()))
+    }
+
+    fn json(kind: FileEventKind) -> serde_json::Value {
+      let mut json = serde_json::to_value(FileEvent::new("dir/file.txt", kind)).unwrap();
+      assert!(json["timestamp"].is_string());
+      json.as_object_mut().unwrap().remove("timestamp");
+      json
+    }
+
+    #[test]
+    fn serializes_events_with_their_path_and_details() {
+      let skipped = FileStatus::Skipped(FileName::new("file.txt"), FileSize::new(10), SkipReason::DestinationExists, progress_bar());
+
+      assert_eq!(
+        json(FileEventKind::from_status(&skipped).unwrap()),
+        serde_json::json!({ "path": "dir/file.txt", "event": "skipped", "bytes": 10, "reason": "destination already exists" })
+      );
+      assert_eq!(
+        json(FileEventKind::from_status(&FileStatus::Flushing(progress_bar())).unwrap()),
+        serde_json::json!({ "path": "dir/file.txt", "event": "flushing" })
+      )
+    }
+
+    #[test]
+    fn serializes_failures_with_their_kind_and_error() {
+      let failed = FileStatus::Failed(FailedReason::ReadFailed(FileName::new("file.txt"), CopyError::new("Input/output error"), progress_bar()));
+
+      assert_eq!(
+        json(FileEventKind::from_status(&failed).unwrap()),
+        serde_json::json!({ "path": "dir/file.txt", "event": "failed", "reason": "read_failed", "error": "Read failed: Input/output error" })
+      )
+    }
+
+    #[test]
+    fn leaves_progress_to_be_aggregated() {
+      assert_eq!(FileEventKind::from_status(&FileStatus::InProgress(100)), None)
+    }
+}
@@ -0,0 +1,90 @@
+use super::FileStatus;
+
+/// Where a file is in its copy. Each file ends in exactly one of the finished states, and stays there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileLifecycle {
+  /// Nothing has been reported for the file yet
+  Pending,
+  Copying,
+  Succeeded,
+  Skipped,
+  Failed,
+}
+
+impl FileLifecycle {
+
+  /// Whether the file has succeeded, been skipped or failed
+  pub fn is_finished(&self) -> bool {
+    matches!(self, FileLifecycle::Succeeded | FileLifecycle::Skipped | FileLifecycle::Failed)
+  }
+
+  /// The state a file is in once `status` has been reported for it,
+  /// or None if a finished file has nothing more to report.
+  pub fn next(self, status: &FileStatus) -> Option<FileLifecycle> {
+    match status {
+      // Directories and extraneous files aren't part of any file's copy
+      FileStatus::CreatedDirectory(..) |
+      FileStatus::CouldNotCreateDirectory(..) |
+      FileStatus::RemovedExtraneous(..) |
+      FileStatus::CouldNotRemoveExtraneous(..) => Some(self),
+
+      _ if self.is_finished() => None,
+
+      FileStatus::Success(..) => Some(FileLifecycle::Succeeded),
+      FileStatus::Skipped(..) => Some(FileLifecycle::Skipped),
+      FileStatus::Failed(_) => Some(FileLifecycle::Failed),
+      _ => Some(FileLifecycle::Copying),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Complete, CopyError, FailedReason, FileName, FileSize, SkipReason};
+    use crate::progress::MyProgressBar;
+    use indicatif::{MultiProgress, ProgressDrawTarget};
+    use pretty_assertions::assert_eq;
+
+    fn progress_bar() -> MyProgressBar {
+      MyProgressBar::new(&MultiProgress::with_draw_target(ProgressDrawTarget::hidden()))
+    }
+
+    fn success() -> FileStatus {
+      FileStatus::Success(FileName::new("file"), FileSize::new(1), progress_bar())
+    }
+
+    fn failure() -> FileStatus {
+      FileStatus::Failed(FailedReason::FlushFailed(FileName::new("file"), CopyError::new("flush failed"), progress_bar()))
+    }
+
+    #[test]
+    fn copies_until_the_file_finishes() {
+      let copying = FileLifecycle::Pending.next(&FileStatus::NotStarted(progress_bar()));
+
+      assert_eq!(copying, Some(FileLifecycle::Copying));
+      assert_eq!(copying.and_then(|s| s.next(&FileStatus::Flushing(progress_bar()))), Some(FileLifecycle::Copying));
+      assert_eq!(copying.and_then(|s| s.next(&success())), Some(FileLifecycle::Succeeded));
+      assert_eq!(copying.and_then(|s| s.next(&failure())), Some(FileLifecycle::Failed))
+    }
+
+    #[test]
+    fn finishes_only_once() {
+      let skipped = FileStatus::Skipped(FileName::new("file"), FileSize::new(1), SkipReason::SameSizeAndModifiedTime, progress_bar());
+
+      for finished in [FileLifecycle::Succeeded, FileLifecycle::Skipped, FileLifecycle::Failed] {
+        assert_eq!(finished.next(&success()), None);
+        assert_eq!(finished.next(&skipped), None);
+        assert_eq!(finished.next(&failure()), None);
+        assert_eq!(finished.next(&FileStatus::CopyComplete(Complete::new(&progress_bar()))), None)
+      }
+    }
+
+    #[test]
+    fn leaves_directory_events_alone() {
+      let created = FileStatus::CreatedDirectory(FileName::new("dir"));
+
+      assert_eq!(FileLifecycle::Pending.next(&created), Some(FileLifecycle::Pending));
+      assert_eq!(FileLifecycle::Failed.next(&created), Some(FileLifecycle::Failed))
+    }
+}
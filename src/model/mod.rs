@@ -1,22 +1,30 @@
 use std::fmt;
 
 mod copy_status;
+mod file_event;
+mod file_lifecycle;
+mod run_summary;
 mod sizes;
 
 pub type R<A> = Result<A, ()>;
 
 pub use copy_status::*;
+pub use file_event::{FileEvent, FileEventKind};
+pub use file_lifecycle::FileLifecycle;
+pub use run_summary::RunSummary;
 pub use sizes::*;
 
 #[derive(Debug)]
 pub enum CoopError {
-  CouldNotOpenLogFile(String)
+  CouldNotOpenLogFile(String),
+  CouldNotOpenEventsFile(String),
 }
 
 impl fmt::Display for CoopError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
       let error = match self {
         CoopError::CouldNotOpenLogFile(e) => format!("Could not open coop.log due to: {e}"),
+        CoopError::CouldNotOpenEventsFile(e) => format!("Could not open the --events file due to: {e}"),
       };
 
       write!(f, "{}", &error)
@@ -0,0 +1,52 @@
+/// What happened to the files of a run, once it has finished.
+#[derive(Debug, Clone, Default)]
+pub struct RunSummary {
+  succeeded: u64,
+  skipped: u64,
+  failures: Vec<Failure>,
+}
+
+/// A file or directory that could not be copied, created or removed, and why
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+  name: String,
+  reason: String,
+}
+
+impl Failure {
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn reason(&self) -> &str {
+    &self.reason
+  }
+}
+
+impl RunSummary {
+
+  pub fn add_success(&mut self) {
+    self.succeeded += 1
+  }
+
+  pub fn add_skip(&mut self) {
+    self.skipped += 1
+  }
+
+  pub fn add_failure(&mut self, name: &str, reason: &str) {
+    self.failures.push(Failure { name: name.to_owned(), reason: reason.to_owned() })
+  }
+
+  pub fn succeeded(&self) -> u64 {
+    self.succeeded
+  }
+
+  pub fn skipped(&self) -> u64 {
+    self.skipped
+  }
+
+  /// In the order they happened
+  pub fn failures(&self) -> &[Failure] {
+    &self.failures
+  }
+}
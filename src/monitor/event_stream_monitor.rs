@@ -0,0 +1,41 @@
+use std::io::Write;
+
+use tokio::sync::mpsc::Receiver;
+
+use crate::args::OutputTarget;
+use crate::model::{CoopError, FileEvent, R};
+
+/// Writes the events of every file as newline-delimited JSON, for --events.
+pub struct EventStreamMonitor {
+  writer: Box<dyn Write + Send>,
+}
+
+impl EventStreamMonitor {
+
+  /// Opens the events file up front, so a file that can't be written is reported before anything is copied.
+  pub fn new(target: &OutputTarget) -> Result<Self, CoopError> {
+    let writer = target.open().map_err(|e| CoopError::CouldNotOpenEventsFile(e.to_string()))?;
+    Ok(Self { writer })
+  }
+
+  /// This is a low cardinality event receiver, as progress is aggregated before it's sent.
+  pub async fn monitor(mut self, mut rx: Receiver<FileEvent>) -> R<()> {
+    while let Some(event) = rx.recv().await {
+      // Each event is flushed, so whatever is reading the stream sees it straight away
+      let written =
+        serde_json::to_writer(&mut self.writer, &event)
+          .map_err(std::io::Error::from)
+          .and_then(|_| self.writer.write_all(b"\n"))
+          .and_then(|_| self.writer.flush());
+
+      if let Err(e) = written {
+        eprintln!("Could not write to the --events stream: {e}");
+        // Keep receiving, so senders aren't blocked by a full channel
+        while rx.recv().await.is_some() {}
+        return Err(())
+      }
+    }
+
+    Ok(())
+  }
+}
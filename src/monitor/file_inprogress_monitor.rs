@@ -1,5 +1,5 @@
 use tokio::sync::mpsc::Receiver;
-use crate::model::{InProgress, R};
+use crate::model::{CopyMethod, InProgress, R};
 
 /// Monitors InProgress messages when a file copy is underway.
 pub struct FileInProgressMonitor;
@@ -10,9 +10,14 @@ impl FileInProgressMonitor {
   pub async fn monitor(mut rx: Receiver<InProgress>) -> R<()> {
     while let Some(progress) = rx.recv().await {
       let bytes_written = progress.bytes_written();
+      let copy_method = progress.copy_method();
       let pb = progress.progress_bar();
       pb.update_progress(bytes_written);
-      pb.set_status("copying...");
+
+      match copy_method {
+        CopyMethod::ReadWrite => pb.set_status("copying..."),
+        method => pb.set_status(&format!("copying with {method}...")),
+      }
     }
 
     Ok(())
@@ -1,5 +1,5 @@
 use tokio::sync::mpsc::Receiver;
-use crate::model::{FileStatus, FailedReason, R};
+use crate::model::{size_pretty, FileStatus, FailedReason, R};
 
 /// Monitors lifecyle events of all file copies in progress.
 pub struct LifecycleEventMonitor;
@@ -16,17 +16,42 @@ impl LifecycleEventMonitor {
           FileStatus::GotDestinationFileLength(pb) => pb.set_status("calculated destination file length"),
           FileStatus::CreatedDestinationFile(pb) => pb.set_status("created destination file"),
           FileStatus::Flushing(pb) => pb.set_status("flushing destination..."),
+          FileStatus::SyncingFile(pb) => pb.set_status("syncing file to disk..."),
+          FileStatus::SyncingDirectory(pb) => pb.set_status("syncing directory to disk..."),
+          FileStatus::Resumed(offset, pb) => pb.set_status(&format!("resuming from {}", size_pretty(offset))),
+          FileStatus::Retrying(_, attempt, error, pb) => {
+            pb.set_status(&format!("retrying ({attempt})"));
+            pb.set_warning(&format!("⚠️ {}", error.error()))
+          },
+          FileStatus::SkippedHole(bytes, pb) => {
+            pb.update_progress(bytes);
+            pb.set_status(&format!("skipped hole of {}", size_pretty(bytes)))
+          },
 
           FileStatus::CopyComplete(complete) => {
             let pb = complete.progress_bar();
             pb.set_status("finished copying")
           },
 
-          FileStatus::FileSizesMatch(pb) => {
+          FileStatus::FileSizesMatch(pb) => pb.set_status("file sizes match"),
+          FileStatus::VerifyingChecksum(pb) => pb.set_status("verifying checksum..."),
+          FileStatus::ChecksumsMatch(_, pb) => pb.set_status("checksums match"),
+          FileStatus::PreservingAttributes(pb) => pb.set_status("preserving attributes..."),
+          FileStatus::RenamingPartialFile(pb) => pb.set_status("moving into place..."),
+          FileStatus::CreatedSymlink(pb) => pb.set_status("created symlink"),
+          FileStatus::CreatedHardLink(pb) => pb.set_status("created hard link"),
+          FileStatus::MovedByRename(_, pb) => pb.set_status("moved by rename"),
+          FileStatus::DeletedSource(pb) => pb.set_status("source deleted"),
+          FileStatus::BackingUpDestination(pb) => pb.set_status("backing up destination..."),
+          FileStatus::BackedUpDestination(_, _, pb) => pb.set_status("backed up destination"),
+
+          FileStatus::Success(_, _, pb) => {
             pb.complete("verification complete ✅");
           },
 
-          FileStatus::Success(..) => (),
+          FileStatus::Skipped(_, _, reason, pb) => {
+            pb.complete(&format!("skipped: {reason} ⏭️"));
+          },
 
           FileStatus::Failed(FailedReason::ReadFailed(_, reason, pb)) => {
             pb.set_error(&format!("❌ Read failed: {}", reason.message()))
@@ -66,6 +91,62 @@ impl LifecycleEventMonitor {
             pb.set_error(&format!("❌ Flushing destination file failed: {}", reason.error()))
           },
 
+          FileStatus::Failed(FailedReason::SyncFailed(_, reason, pb)) => {
+            pb.set_status("syncing to disk...");
+            pb.set_error(&format!("❌ Could not sync to disk: {}", reason.error()))
+          },
+
+          FileStatus::Failed(FailedReason::CouldNotCalculateChecksum(_, reason, pb)) => {
+            pb.set_status("verifying checksum...");
+            pb.set_error(&format!("❌ Could not calculate checksum: {}", reason.error()))
+          },
+
+          FileStatus::Failed(FailedReason::ChecksumsAreDifferent(_, comparison, pb)) => {
+            pb.set_status("verifying checksum...");
+            pb.set_error(&format!("❌ Checksums are different. src:{}, dst:{}", comparison.source_checksum(), comparison.destination_checksum()))
+          },
+
+          FileStatus::Failed(FailedReason::CouldNotPreserveAttributes(_, reason, pb)) => {
+            pb.set_status("preserving attributes...");
+            pb.set_error(&format!("❌ Could not preserve attributes: {}", reason.error()))
+          },
+
+          FileStatus::Failed(FailedReason::CouldNotRenamePartialFile(_, reason, pb)) => {
+            pb.set_status("moving into place...");
+            pb.set_error(&format!("❌ Could not move partial file into place: {}", reason.error()))
+          },
+
+          FileStatus::Failed(FailedReason::CouldNotCreateSymlink(_, reason, pb)) => {
+            pb.set_status("creating symlink...");
+            pb.set_error(&format!("❌ Could not create symlink: {}", reason.error()))
+          },
+
+          FileStatus::Failed(FailedReason::CouldNotCreateHardLink(_, reason, pb)) => {
+            pb.set_status("creating hard link...");
+            pb.set_error(&format!("❌ Could not create hard link: {}", reason.error()))
+          },
+
+          FileStatus::Failed(FailedReason::CouldNotBackUpDestination(_, reason, pb)) => {
+            pb.set_status("backing up destination...");
+            pb.set_error(&format!("❌ Could not back up destination: {}", reason.error()))
+          },
+
+          FileStatus::Warning(FailedReason::CouldNotCopyExtendedAttributes(_, reason, pb)) => {
+            pb.set_warning(&format!("⚠️ Could not copy extended attributes: {}", reason.error()))
+          },
+
+          FileStatus::Warning(FailedReason::CouldNotDeleteSource(_, reason, pb)) => {
+            pb.set_warning(&format!("⚠️ Could not delete source: {}", reason.error()))
+          },
+
+          FileStatus::Warning(_) => (),
+          // These are only ever sent as warnings, once the file itself has been copied
+          FileStatus::Failed(FailedReason::CouldNotCopyExtendedAttributes(..) | FailedReason::CouldNotDeleteSource(..)) => (),
+
+          // Directories and removals in mirror mode don't have a progress bar
+          FileStatus::CreatedDirectory(..) | FileStatus::CouldNotCreateDirectory(..) => (),
+          FileStatus::RemovedExtraneous(..) | FileStatus::CouldNotRemoveExtraneous(..) => (),
+
           FileStatus::InProgress(..) => ()
         }
       }
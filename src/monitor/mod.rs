@@ -2,8 +2,12 @@ mod lifecycle_event_monitor;
 mod overall_progress_monitor;
 mod file_inprogress_monitor;
 mod monitor_mux;
+mod event_stream_monitor;
+mod report_monitor;
 
 pub use lifecycle_event_monitor::LifecycleEventMonitor;
 pub use overall_progress_monitor::{OverallProgressMonitor, TotalFileSize, NumFiles};
-pub use monitor_mux::{MonitorMux, LifecycleEventSender, OverallProgressSender, InProgressSender};
+pub use monitor_mux::{MonitorMux, LifecycleEventSender, OverallProgressSender, InProgressSender, EventSender};
 pub use file_inprogress_monitor::FileInProgressMonitor;
+pub use event_stream_monitor::EventStreamMonitor;
+pub use report_monitor::ReportMonitor;
@@ -1,11 +1,14 @@
 use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
 
 use tokio::sync::mpsc::{self};
 
-use crate::model::{Complete, CopyError, FailedReason, FileName, FileSize, FileStatus, InProgress, SizeComparison};
+use crate::model::{ChecksumComparison, Complete, CopyError, CopyMethod, FailedReason, FileEvent, FileEventKind, FileLifecycle, FileName, FileSize, FileStatus, InProgress, RetryAttempt, SizeComparison, SkipReason};
 use crate::progress::MyProgressBar;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LifecycleEventSender(mpsc::Sender<FileStatus>);
 
 impl LifecycleEventSender {
@@ -22,7 +25,7 @@ impl Deref for LifecycleEventSender {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OverallProgressSender(mpsc::Sender<FileStatus>);
 
 impl OverallProgressSender {
@@ -40,7 +43,7 @@ impl Deref for OverallProgressSender {
   }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct InProgressSender(mpsc::Sender<InProgress>);
 
 impl InProgressSender {
@@ -58,12 +61,104 @@ impl Deref for InProgressSender {
   }
 }
 
+/// Sends the events of a file to the event stream, aggregating its progress so there's at most one progress event per interval.
+#[derive(Debug, Clone)]
+pub struct EventSender {
+  /// Such as the event stream and the report, which each get every event
+  senders: Vec<mpsc::Sender<FileEvent>>,
+  /// The file events are sent for, relative to the source. Directory and extraneous file events carry their own paths.
+  path: String,
+  /// Shared with clones, such as those used by the byte ranges of a file
+  progress: Arc<Mutex<AggregatedProgress>>,
+}
+
+#[derive(Debug, Default)]
+struct AggregatedProgress {
+  unsent_bytes: u64,
+  copied_bytes: u64,
+  last_sent: Option<Instant>,
+}
+
+impl EventSender {
+
+  const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+  pub fn new(senders: Vec<mpsc::Sender<FileEvent>>) -> Self {
+    Self {
+      senders,
+      path: String::new(),
+      progress: Arc::default(),
+    }
+  }
+
+  /// A sender for the events of a single file
+  pub fn for_file(&self, path: &str) -> Self {
+    Self {
+      senders: self.senders.clone(),
+      path: path.to_owned(),
+      progress: Arc::default(),
+    }
+  }
 
-#[derive(Debug)]
+  async fn send(&self, status: &FileStatus) {
+    let path =
+      match status {
+        FileStatus::CreatedDirectory(name) |
+        FileStatus::CouldNotCreateDirectory(name, _) |
+        FileStatus::RemovedExtraneous(name, _) |
+        FileStatus::CouldNotRemoveExtraneous(name, _) => name.clone().name(),
+        _ => self.path.clone()
+      };
+
+    let events: Vec<FileEventKind> = {
+      let mut progress = self.progress.lock().unwrap_or_else(PoisonError::into_inner);
+
+      match (status, FileEventKind::from_status(status)) {
+        (FileStatus::InProgress(bytes), _) => {
+          progress.unsent_bytes += bytes;
+          progress.copied_bytes += bytes;
+
+          if progress.last_sent.is_none_or(|sent| sent.elapsed() >= Self::PROGRESS_INTERVAL) {
+            Self::take_progress(&mut progress).into_iter().collect()
+          } else {
+            vec![]
+          }
+        },
+        // Progress that hasn't been sent yet comes first, so it adds up by the time the file finishes
+        (_, Some(kind)) => Self::take_progress(&mut progress).into_iter().chain([kind]).collect(),
+        (_, None) => vec![]
+      }
+    };
+
+    for kind in events {
+      let event = FileEvent::new(&path, kind);
+
+      for sender in &self.senders {
+        let _ = sender.send(event.clone()).await;
+      }
+    }
+  }
+
+  fn take_progress(progress: &mut AggregatedProgress) -> Option<FileEventKind> {
+    if progress.unsent_bytes == 0 {
+      return None
+    }
+
+    let event = FileEventKind::Progress { bytes: progress.unsent_bytes, copied: progress.copied_bytes };
+    progress.unsent_bytes = 0;
+    progress.last_sent = Some(Instant::now());
+    Some(event)
+  }
+}
+
+#[derive(Debug, Clone)]
 pub struct MonitorMux {
   lifecycle_event_sender: LifecycleEventSender,
   overall_progress_sender: OverallProgressSender,
   inprogress_sender: InProgressSender,
+  /// Shared with clones, such as those used by the byte ranges of a file
+  lifecycle: Arc<Mutex<FileLifecycle>>,
+  event_sender: Option<EventSender>,
 }
 
 impl MonitorMux {
@@ -76,64 +171,91 @@ impl MonitorMux {
     Self {
       lifecycle_event_sender,
       overall_progress_sender,
-      inprogress_sender
+      inprogress_sender,
+      lifecycle: Arc::new(Mutex::new(FileLifecycle::Pending)),
+      event_sender: None,
+    }
+  }
+
+  /// Also sends every status to the event stream, if there is one
+  pub fn with_event_sender(self, event_sender: Option<EventSender>) -> Self {
+    Self {
+      event_sender,
+      ..self
+    }
+  }
+
+  /// Moves the file on to the state that follows `status`, returning false if the file has already finished.
+  fn advance(&self, status: &FileStatus) -> bool {
+    let mut lifecycle = self.lifecycle.lock().unwrap_or_else(PoisonError::into_inner);
+
+    match lifecycle.next(status) {
+      Some(next) => {
+        *lifecycle = next;
+        true
+      },
+      None => false
+    }
+  }
+
+  /// Sends a status to the lifecycle and overall progress monitors.
+  /// Once a file has succeeded, been skipped or failed, nothing more is sent for it, so it is only counted once.
+  async fn send(&self, status: FileStatus) {
+    if self.advance(&status) {
+      if let Some(event_sender) = &self.event_sender {
+        event_sender.send(&status).await
+      }
+
+      let _ = self.lifecycle_event_sender.send(status.clone()).await;
+      let _ = self.overall_progress_sender.send(status).await;
     }
   }
 
   pub async fn send_opened_source_file(&self, progress_bar: &MyProgressBar) {
-    let _ = &self.lifecycle_event_sender.send(FileStatus::OpenedSourceFile(progress_bar.clone())).await;
-    let _ = &self.overall_progress_sender.send(FileStatus::OpenedSourceFile(progress_bar.clone())).await;
-  }
-
-  pub async fn send_could_not_read_source_file<F: Into<FileName> + Clone, E: Into<CopyError> + Clone>(&self, file: F, error: E, progress_bar: &MyProgressBar) {
-    let _ =
-      &self.lifecycle_event_sender.send(
-        FileStatus::Failed(
-          FailedReason::CouldNotReadSourceFile(
-            file.clone().into(),
-            error.clone().into(),
-            progress_bar.clone()
-          )
-        )
-      ).await;
-
-    let _ =
-      &self.overall_progress_sender.send(
-        FileStatus::Failed(
-          FailedReason::CouldNotReadSourceFile(
-            file.into(),
-            error.into(),
-            progress_bar.clone()
-          )
+    self.send(FileStatus::OpenedSourceFile(progress_bar.clone())).await;
+  }
+
+  pub async fn send_could_not_read_source_file<E: Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::CouldNotReadSourceFile(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
+      )
+    ).await;
   }
 
   pub async fn send_getting_file_length(&self, progress_bar: &MyProgressBar) {
-    let _ = self.lifecycle_event_sender.send(FileStatus::GettingDestinationFileLength(progress_bar.clone())).await;
-    let _ = self.overall_progress_sender.send(FileStatus::GettingDestinationFileLength(progress_bar.clone())).await;
+    self.send(FileStatus::GettingDestinationFileLength(progress_bar.clone())).await;
   }
 
   pub async fn send_got_file_length(&self, progress_bar: &MyProgressBar) {
-    let _ = self.lifecycle_event_sender.send(FileStatus::GotDestinationFileLength(progress_bar.clone())).await;
-    let _ = self.overall_progress_sender.send(FileStatus::GotDestinationFileLength(progress_bar.clone())).await;
+    self.send(FileStatus::GotDestinationFileLength(progress_bar.clone())).await;
   }
 
-  pub async fn send_could_not_get_destination_file_size<E: Into<CopyError> + Clone>(&self, file_name: &str, error: E, progress_bar: &MyProgressBar) {
-    let _ = self.lifecycle_event_sender.send(
+  pub async fn send_could_not_get_destination_file_size<E: Into<CopyError>>(&self, file_name: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
       FileStatus::Failed(
         FailedReason::CouldNotGetDestinationFileSize(
           FileName::new(file_name),
-          error.clone().into(),
+          error.into(),
           progress_bar.clone()
         )
       )
     ).await;
+  }
+
+  pub async fn send_not_started(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::NotStarted(progress_bar.clone())).await;
+  }
 
-    let _ = self.overall_progress_sender.send(
+  pub async fn send_could_not_create_destination_directory<E: Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
       FileStatus::Failed(
-        FailedReason::CouldNotGetDestinationFileSize(
-          FileName::new(file_name),
+        FailedReason::CouldNotCreateDestinationDir(
+          FileName::new(file),
           error.into(),
           progress_bar.clone()
         )
@@ -141,179 +263,365 @@ impl MonitorMux {
     ).await;
   }
 
-  pub async fn send_not_started(&self, progress_bar: &MyProgressBar) {
-    let _ = self.lifecycle_event_sender.send(FileStatus::NotStarted(progress_bar.clone())).await;
-    let _ = self.overall_progress_sender.send(FileStatus::NotStarted(progress_bar.clone())).await;
-  }
-
-  pub async fn send_could_not_create_destination_directory<P: AsRef<std::path::Path> + Clone, E: Into<CopyError> + Clone>(&self, destination_file: P, error: E, progress_bar: &MyProgressBar) {
-    let _ =
-      self.lifecycle_event_sender.send(
-        FileStatus::Failed(
-          FailedReason::CouldNotCreateDestinationDir(
-            destination_file.clone().into(),
-            error.clone().into(),
-            progress_bar.clone()
-          )
+  pub async fn send_created_destination_file(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::CreatedDestinationFile(progress_bar.clone())).await;
+  }
+
+  pub async fn send_could_not_create_destination_file<E: Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::CouldNotCreateDestinationFile(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
-
-    let _ =
-      self.overall_progress_sender.send(
-        FileStatus::Failed(
-          FailedReason::CouldNotCreateDestinationDir(
-            destination_file.into(),
-            error.into(),
-            progress_bar.clone()
-          )
+      )
+    ).await;
+  }
+
+  pub async fn send_read_failed<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::ReadFailed(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone())
+      )
+    ).await;
+  }
+
+  pub async fn send_flushing_destination_file(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::Flushing(progress_bar.clone())).await;
+  }
+
+  pub async fn send_syncing_file(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::SyncingFile(progress_bar.clone())).await;
+  }
+
+  pub async fn send_syncing_directory(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::SyncingDirectory(progress_bar.clone())).await;
+  }
+
+  pub async fn send_sync_failed<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::SyncFailed(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
+      )
+    ).await;
   }
 
-  pub async fn send_created_destination_file(&self, progress_bar: &MyProgressBar) {
-    let _ = self.lifecycle_event_sender.send(FileStatus::CreatedDestinationFile(progress_bar.clone())).await;
-    let _ = self.overall_progress_sender.send(FileStatus::CreatedDestinationFile(progress_bar.clone())).await;
-  }
-
-  pub async fn send_could_not_create_destination_file<P: AsRef<std::path::Path> + Clone, E: Into<CopyError> + Clone>(&self, destination_file: P, error: E, progress_bar: &MyProgressBar) {
-    let _ =
-      self.lifecycle_event_sender.send(
-        FileStatus::Failed(
-          FailedReason::CouldNotCreateDestinationFile(
-            destination_file.clone().into(),
-            error.clone().into(),
-            progress_bar.clone()
-          )
+  pub async fn send_flushing_to_destination_file_failed<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::FlushFailed(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone())
+      )
+    ).await;
+  }
+
+  pub async fn send_copy_complete(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::CopyComplete(Complete::new(progress_bar))).await;
+  }
+
+  pub async fn send_file_sizes_match(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::FileSizesMatch(progress_bar.clone())).await;
+  }
+
+  pub async fn send_files_sizes_are_different(&self, file: &str, size_comparison: SizeComparison, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::FileSizesAreDifferent(
+          FileName::new(file),
+          size_comparison,
+          progress_bar.clone()
         )
-      ).await;
-
-    let _ =
-      self.overall_progress_sender.send(
-        FileStatus::Failed(
-          FailedReason::CouldNotCreateDestinationFile(
-            destination_file.into(),
-            error.into(),
-            progress_bar.clone()
-          )
+      )
+    ).await;
+  }
+
+  pub async fn send_verifying_checksum(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::VerifyingChecksum(progress_bar.clone())).await;
+  }
+
+  pub async fn send_checksums_match(&self, checksum: &str, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::ChecksumsMatch(checksum.to_owned(), progress_bar.clone())).await;
+  }
+
+  pub async fn send_could_not_calculate_checksum<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::CouldNotCalculateChecksum(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
+      )
+    ).await;
   }
 
-  pub async fn send_read_failed<E : Into<CopyError> + Clone>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
-    let _ =
-      self.lifecycle_event_sender.send(
-        FileStatus::Failed(
-          FailedReason::ReadFailed(
-            FileName::new(file),
-            error.clone().into(),
-            progress_bar.clone())
+  pub async fn send_checksums_are_different(&self, file: &str, checksum_comparison: ChecksumComparison, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::ChecksumsAreDifferent(
+          FileName::new(file),
+          checksum_comparison,
+          progress_bar.clone()
         )
-      ).await;
-
-    let _ =
-      self.overall_progress_sender.send(
-        FileStatus::Failed(
-          FailedReason::ReadFailed(
-            FileName::new(file),
-            error.into(),
-            progress_bar.clone())
+      )
+    ).await;
+  }
+
+  pub async fn send_preserving_attributes(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::PreservingAttributes(progress_bar.clone())).await;
+  }
+
+  pub async fn send_could_not_preserve_attributes<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::CouldNotPreserveAttributes(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
+      )
+    ).await;
   }
 
-  pub async fn send_flushing_destination_file(&self, progress_bar: &MyProgressBar) {
-    let _ = self.lifecycle_event_sender.send(FileStatus::Flushing(progress_bar.clone())).await;
-    let _ = self.overall_progress_sender.send(FileStatus::Flushing(progress_bar.clone())).await;
-  }
-
-  pub async fn send_flushing_to_destination_file_failed<E : Into<CopyError> + Clone>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
-    let _ =
-      self.lifecycle_event_sender.send(
-        FileStatus::Failed(
-          FailedReason::FlushFailed(
-            FileName::new(file),
-            error.clone().into(),
-            progress_bar.clone())
+  pub async fn send_could_not_copy_extended_attributes<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Warning(
+        FailedReason::CouldNotCopyExtendedAttributes(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
-
-    let _ =
-      self.overall_progress_sender.send(
-        FileStatus::Failed(
-          FailedReason::FlushFailed(
-            FileName::new(file),
-            error.into(),
-            progress_bar.clone())
+      )
+    ).await;
+  }
+
+  pub async fn send_moved_by_rename(&self, file_size: u64, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::MovedByRename(file_size, progress_bar.clone())).await;
+  }
+
+  pub async fn send_deleted_source(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::DeletedSource(progress_bar.clone())).await;
+  }
+
+  pub async fn send_could_not_delete_source<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Warning(
+        FailedReason::CouldNotDeleteSource(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
+      )
+    ).await;
   }
 
-  pub async fn send_copy_complete(&self, progress_bar: &MyProgressBar) {
-    let _ = self.lifecycle_event_sender.send(FileStatus::CopyComplete(Complete::new(progress_bar))).await;
-    let _ = self.overall_progress_sender.send(FileStatus::CopyComplete(Complete::new(progress_bar))).await;
+  pub async fn send_backing_up_destination(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::BackingUpDestination(progress_bar.clone())).await;
   }
 
-  pub async fn send_file_sizes_match(&self, progress_bar: &MyProgressBar) {
-    let _ = self.lifecycle_event_sender.send(FileStatus::FileSizesMatch(progress_bar.clone())).await;
-    let _ = self.overall_progress_sender.send(FileStatus::FileSizesMatch(progress_bar.clone())).await;
+  pub async fn send_backed_up_destination(&self, file: &str, backup_file: PathBuf, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::BackedUpDestination(FileName::new(file), backup_file, progress_bar.clone())).await;
   }
 
-  pub async fn send_files_sizes_are_different(&self, file: &str, size_comparison: SizeComparison, progress_bar: &MyProgressBar) {
-    let _ =
-      self.lifecycle_event_sender.send(
-        FileStatus::Failed(
-          FailedReason::FileSizesAreDifferent(
-            FileName::new(file),
-            size_comparison.clone(),
-            progress_bar.clone()
-          )
+  pub async fn send_could_not_back_up_destination<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::CouldNotBackUpDestination(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
+        )
+      )
+    ).await;
+  }
+
+  pub async fn send_created_directory(&self, directory: &str) {
+    self.send(FileStatus::CreatedDirectory(FileName::new(directory))).await;
+  }
+
+  pub async fn send_could_not_create_directory<E : Into<CopyError>>(&self, directory: &str, error: E) {
+    self.send(FileStatus::CouldNotCreateDirectory(FileName::new(directory), error.into())).await;
+  }
+
+  pub async fn send_removed_extraneous(&self, file: &str, trashed_file: Option<PathBuf>) {
+    self.send(FileStatus::RemovedExtraneous(FileName::new(file), trashed_file)).await;
+  }
+
+  pub async fn send_could_not_remove_extraneous<E : Into<CopyError>>(&self, file: &str, error: E) {
+    self.send(FileStatus::CouldNotRemoveExtraneous(FileName::new(file), error.into())).await;
+  }
+
+  pub async fn send_renaming_partial_file(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::RenamingPartialFile(progress_bar.clone())).await;
+  }
+
+  pub async fn send_could_not_rename_partial_file<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::CouldNotRenamePartialFile(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
+        )
+      )
+    ).await;
+  }
+
+  pub async fn send_created_symlink(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::CreatedSymlink(progress_bar.clone())).await;
+  }
+
+  pub async fn send_could_not_create_symlink<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::CouldNotCreateSymlink(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
-
-    let _ =
-      self.overall_progress_sender.send(
-        FileStatus::Failed(
-          FailedReason::FileSizesAreDifferent(
-            FileName::new(file),
-            size_comparison,
-            progress_bar.clone()
-          )
+      )
+    ).await;
+  }
+
+  pub async fn send_created_hard_link(&self, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::CreatedHardLink(progress_bar.clone())).await;
+  }
+
+  pub async fn send_could_not_create_hard_link<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::CouldNotCreateHardLink(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
+      )
+    ).await;
   }
 
   pub async fn send_success(&self, file_name: &str, file_size: u64, progress_bar: &MyProgressBar) {
-    let _ = self.lifecycle_event_sender.send(FileStatus::Success(FileName::new(file_name), FileSize::new(file_size), progress_bar.clone())).await;
-    let _ = self.overall_progress_sender.send(FileStatus::Success(FileName::new(file_name), FileSize::new(file_size), progress_bar.clone())).await;
-  }
-
-  pub async fn send_write_to_destination_failed<E : Into<CopyError> + Clone>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
-    let _ =
-      self.lifecycle_event_sender.send(
-        FileStatus::Failed(
-          FailedReason::WriteFailed(
-            FileName::new(file),
-            error.clone().into(),
-            progress_bar.clone()
-          )
-        )
-      ).await;
-
-    let _ =
-      self.overall_progress_sender.send(
-        FileStatus::Failed(
-          FailedReason::WriteFailed(
-            FileName::new(file),
-            error.into(),
-            progress_bar.clone()
-          )
+    self.send(FileStatus::Success(FileName::new(file_name), FileSize::new(file_size), progress_bar.clone())).await;
+  }
+
+  pub async fn send_write_to_destination_failed<E : Into<CopyError>>(&self, file: &str, error: E, progress_bar: &MyProgressBar) {
+    self.send(
+      FileStatus::Failed(
+        FailedReason::WriteFailed(
+          FileName::new(file),
+          error.into(),
+          progress_bar.clone()
         )
-      ).await;
+      )
+    ).await;
+  }
+
+  pub async fn send_skipped(&self, file_name: &str, file_size: u64, reason: SkipReason, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::Skipped(FileName::new(file_name), FileSize::new(file_size), reason, progress_bar.clone())).await;
+  }
+
+  pub async fn send_resumed(&self, offset: u64, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::Resumed(offset, progress_bar.clone())).await;
+  }
+
+  pub async fn send_skipped_hole(&self, bytes: u64, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::SkippedHole(bytes, progress_bar.clone())).await;
   }
 
-  pub async fn send_copy_in_progress(&self, bytes_written: u64, progress_bar: &MyProgressBar) {
-    let _ = self.inprogress_sender.send(InProgress::new(bytes_written, progress_bar)).await;
+  pub async fn send_retrying<E : Into<CopyError>>(&self, file: &str, attempt: RetryAttempt, error: E, progress_bar: &MyProgressBar) {
+    self.send(FileStatus::Retrying(FileName::new(file), attempt, error.into(), progress_bar.clone())).await;
+  }
+
+  pub async fn send_copy_in_progress(&self, bytes_written: u64, copy_method: CopyMethod, progress_bar: &MyProgressBar) {
+    let _ = self.inprogress_sender.send(InProgress::new(bytes_written, copy_method, progress_bar)).await;
+
     // Only send this to the overall progress monitor
-    let _ = self.overall_progress_sender.send(FileStatus::InProgress(bytes_written)).await;
+    if self.advance(&FileStatus::InProgress(bytes_written)) {
+      if let Some(event_sender) = &self.event_sender {
+        event_sender.send(&FileStatus::InProgress(bytes_written)).await
+      }
+
+      let _ = self.overall_progress_sender.send(FileStatus::InProgress(bytes_written)).await;
+    }
   }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indicatif::{MultiProgress, ProgressDrawTarget};
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn sends_nothing_once_a_file_has_failed() {
+      let (lifecycle_sender, mut lifecycle_receiver) = mpsc::channel(100);
+      let (overall_sender, mut overall_receiver) = mpsc::channel(100);
+      let mux =
+        MonitorMux::new(
+          InProgressSender::new(mpsc::channel(100).0),
+          LifecycleEventSender::new(lifecycle_sender),
+          OverallProgressSender::new(overall_sender)
+        );
+      let progress_bar = MyProgressBar::new(&MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+
+      mux.send_flushing_destination_file(&progress_bar).await;
+      mux.send_flushing_to_destination_file_failed("file", CopyError::new("flush failed"), &progress_bar).await;
+      mux.send_copy_complete(&progress_bar).await;
+      mux.send_copy_in_progress(10, CopyMethod::ReadWrite, &progress_bar).await;
+      mux.clone().send_success("file", 10, &progress_bar).await;
+      drop(mux);
+
+      for receiver in [&mut lifecycle_receiver, &mut overall_receiver] {
+        let mut statuses = vec![];
+        while let Some(status) = receiver.recv().await {
+          statuses.push(status)
+        }
+
+        assert!(matches!(statuses[..], [FileStatus::Flushing(_), FileStatus::Failed(FailedReason::FlushFailed(..))]))
+      }
+    }
+
+    #[tokio::test]
+    async fn aggregates_progress_events_of_a_file() {
+      let (event_sender, mut event_receiver) = mpsc::channel(100);
+      let mux =
+        MonitorMux::new(
+          InProgressSender::new(mpsc::channel(100).0),
+          LifecycleEventSender::new(mpsc::channel(100).0),
+          OverallProgressSender::new(mpsc::channel(100).0)
+        )
+        .with_event_sender(Some(EventSender::new(vec![event_sender]).for_file("dir/file.txt")));
+      let progress_bar = MyProgressBar::new(&MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+
+      for _ in 0..10 {
+        mux.send_copy_in_progress(10, CopyMethod::ReadWrite, &progress_bar).await;
+      }
+      mux.send_success("file.txt", 100, &progress_bar).await;
+      drop(mux);
+
+      let mut events = vec![];
+      while let Some(event) = event_receiver.recv().await {
+        events.push(serde_json::to_value(event).unwrap())
+      }
+
+      let events: Vec<_> = events.iter().map(|e| (e["path"].as_str(), e["event"].as_str(), e["bytes"].as_u64(), e["copied"].as_u64())).collect();
+      assert_eq!(
+        events,
+        vec![
+          (Some("dir/file.txt"), Some("progress"), Some(10), Some(10)),
+          // The rest arrived within the interval, so they are sent just before the file finishes
+          (Some("dir/file.txt"), Some("progress"), Some(90), Some(100)),
+          (Some("dir/file.txt"), Some("success"), Some(100), None),
+        ]
+      )
+    }
+}
@@ -1,5 +1,6 @@
 use std::fs::{File, OpenOptions};
 use std::io::Write;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
 use std::time::{Duration, Instant};
@@ -8,15 +9,20 @@ use chrono::TimeDelta;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use tokio::sync::mpsc::Receiver;
 
-use crate::model::{size_pretty, CoopError, CopyError, FailedReason, FileName, FileSize, FileStatus, R};
+use crate::copy::BandwidthLimiter;
+use crate::model::{size_pretty, CoopError, CopyError, FailedReason, FileName, FileSize, FileStatus, RetryAttempt, RunSummary, SkipReason};
 
 struct State {
   completed: u64,
   completed_bytes: u64,
   inprogress_bytes: u64,
+  /// Bytes that were already at the destination and didn't need copying, such as resumed offsets and skipped files.
+  skipped_bytes: u64,
+  created_directories: u64,
   log: File,
   error_bar: ProgressBar,
   errors: Vec<String>,
+  summary: RunSummary,
 }
 
 pub struct NumFiles(u64);
@@ -42,12 +48,13 @@ pub struct OverallProgressMonitor {
   items: u64,
   total_bytes: u64,
   state: Arc<Mutex<State>>,
-  start_time: Option<Instant>
+  start_time: Option<Instant>,
+  bandwidth_limiter: Arc<BandwidthLimiter>
 }
 
 impl OverallProgressMonitor {
 
-  pub fn new(multi: &MultiProgress, num_files: NumFiles, total_file_size: TotalFileSize) -> Result<Self, CoopError> {
+  pub fn new(multi: &MultiProgress, num_files: NumFiles, total_file_size: TotalFileSize, bandwidth_limiter: Arc<BandwidthLimiter>) -> Result<Self, CoopError> {
     let overall_bar_style =
       ProgressStyle::with_template("[{msg}] {prefix} [{bar:.green}]").unwrap();
 
@@ -96,9 +103,12 @@ impl OverallProgressMonitor {
             completed: 0,
             completed_bytes: 0,
             inprogress_bytes: 0,
+            skipped_bytes: 0,
+            created_directories: 0,
             log,
             error_bar,
-            errors: vec![]
+            errors: vec![],
+            summary: RunSummary::default()
           }
         )
       );
@@ -110,19 +120,21 @@ impl OverallProgressMonitor {
         items: num_files.0,
         total_bytes: total_file_size.0,
         state,
-        start_time: None
+        start_time: None,
+        bandwidth_limiter
       }
     )
   }
 
 
   /// This is a low cardinality event receiver.
-  pub async fn monitor(mut self, mut rx: Receiver<FileStatus>, start_time: Instant) -> R<()> {
+  /// Returns what happened to every file once all senders have been dropped.
+  pub async fn monitor(mut self, mut rx: Receiver<FileStatus>, start_time: Instant) -> RunSummary {
     self.start_time = Some(start_time); // Set the start time
     {
       let state_guard = self.state.lock().unwrap();
       self.set_progress(&state_guard);
-      Self::set_stats(&state_guard, &self.stats_bar, self.total_bytes, start_time);
+      Self::set_stats(&state_guard, &self.stats_bar, self.total_bytes, start_time, &self.bandwidth_limiter);
       drop(state_guard)
     }
 
@@ -145,10 +157,11 @@ impl OverallProgressMonitor {
     let inprogress_handle = {
       let pb = self.stats_bar.clone();
       let state = self.state.clone();
+      let bandwidth_limiter = self.bandwidth_limiter.clone();
       thread::spawn(move || {
         while !pb.is_finished() {
           let guard = state.lock().unwrap();
-          Self::set_stats(&guard, &pb, self.total_bytes, start_time);
+          Self::set_stats(&guard, &pb, self.total_bytes, start_time, &bandwidth_limiter);
           drop(guard);
           thread::sleep(Duration::from_secs(1));
         }
@@ -162,38 +175,128 @@ impl OverallProgressMonitor {
           self.handle_succeeded(file_name, file_size)
         },
 
-        FileStatus::Failed(FailedReason::ReadFailed(file_name, error, _)) => self.handle_failed(file_name, error),
-        FileStatus::Failed(FailedReason::WriteFailed(file_name, error, _)) => self.handle_failed(file_name, error),
-        FileStatus::Failed(FailedReason::FlushFailed(file_name, error, _)) => self.handle_failed(file_name, error),
-        FileStatus::Failed(FailedReason::CouldNotReadSourceFile(file_name, error, _)) => self.handle_failed(file_name, error),
-        FileStatus::Failed(FailedReason::CouldNotGetDestinationFileSize(file_name, error, _)) => self.handle_failed(file_name, error),
-        FileStatus::Failed(FailedReason::CouldNotCreateDestinationFile(file_name, error, _)) => self.handle_failed(file_name, error),
-        FileStatus::Failed(FailedReason::CouldNotCreateDestinationDir(file_name, error, _)) => self.handle_failed(file_name, error),
-        FileStatus::Failed(FailedReason::FileSizesAreDifferent(file_name, _, _)) => self.handle_failed(file_name, CopyError::new("File sizes are different")),
+        FileStatus::Skipped(file_name, file_size, reason, _) => {
+          self.handle_skipped(file_name, file_size, reason)
+        },
+
+        FileStatus::Failed(reason) => self.handle_failed(reason),
+        FileStatus::Warning(FailedReason::CouldNotCopyExtendedAttributes(file_name, error, _)) => {
+          self.handle_warning(file_name, &format!("Could not copy extended attributes: {}", error.error()))
+        },
+        FileStatus::Warning(FailedReason::CouldNotDeleteSource(file_name, error, _)) => {
+          self.handle_warning(file_name, &format!("Could not delete source: {}", error.error()))
+        },
         FileStatus::InProgress(bytes) => self.handle_inprogress(bytes),
+        FileStatus::MovedByRename(bytes, _) => self.handle_skipped_bytes(bytes),
+        FileStatus::Resumed(bytes, _) => self.handle_skipped_bytes(bytes),
+        FileStatus::SkippedHole(bytes, _) => self.handle_skipped_bytes(bytes),
+        FileStatus::Retrying(file_name, attempt, error, _) => self.handle_retrying(file_name, attempt, error),
+        FileStatus::BackedUpDestination(file_name, backup_file, _) => self.handle_backed_up(file_name, backup_file),
+        FileStatus::CreatedDirectory(directory) => self.handle_created_directory(directory),
+        FileStatus::CouldNotCreateDirectory(directory, error) => {
+          let mut state_guard = self.state.lock().unwrap();
+          let (directory, reason) = (format!("{}/", directory.name()), format!("Could not create directory: {}", error.error()));
+          state_guard.summary.add_failure(&directory, &reason);
+          Self::insert_failed_bar(&directory, &reason, &mut state_guard)
+        },
+        FileStatus::RemovedExtraneous(file_name, trashed_file) => self.handle_removed_extraneous(file_name, trashed_file),
+        FileStatus::CouldNotRemoveExtraneous(file_name, error) => {
+          let mut state_guard = self.state.lock().unwrap();
+          let (file, reason) = (file_name.name(), format!("Could not remove extraneous file: {}", error.error()));
+          state_guard.summary.add_failure(&file, &reason);
+          Self::insert_failed_bar(&file, &reason, &mut state_guard)
+        },
         _ => ()
      }
     }
 
+    // Nothing is completed when there are no files, such as when only directories are created
+    if !self.overall_bar.is_finished() {
+      self.overall_bar.finish();
+      self.stats_bar.finish();
+    }
+
     let _ = timer_handle.join();
     let _ = inprogress_handle.join();
 
-    Ok(())
+    let summary = self.state.lock().unwrap().summary.clone();
+    summary
   }
 
 
   fn handle_succeeded(&self, file: FileName, file_size: FileSize) {
-    self.handle_end_state(Some(file_size.clone()), |state| Self::insert_completed_bar(&file.name(), file_size, state))
+    self.handle_end_state(Some(file_size.clone()), |state| {
+      state.summary.add_success();
+      Self::insert_completed_bar(&file.name(), file_size, state)
+    })
   }
 
-  fn handle_failed(&self, file: FileName, error: CopyError) {
-    self.handle_end_state(None, |state| Self::insert_failed_bar(&file.name(), &error.error(), state))
+  fn handle_skipped(&self, file: FileName, file_size: FileSize, reason: SkipReason) {
+    self.handle_skipped_bytes(file_size.clone().size());
+    self.handle_end_state(Some(file_size.clone()), |state| {
+      state.summary.add_skip();
+      Self::insert_skipped_bar(&file.name(), file_size, reason, state)
+    })
+  }
+
+  fn handle_failed(&self, reason: FailedReason) {
+    let file = reason.file_name().clone().name();
+    let reason = reason.to_string();
+
+    self.handle_end_state(None, |state| {
+      state.summary.add_failure(&file, &reason);
+      Self::insert_failed_bar(&file, &reason, state)
+    })
+  }
+
+  fn handle_warning(&self, file: FileName, warning: &str) {
+    let mut state_guard = self.state.lock().unwrap();
+    Self::insert_warning_bar(&file.name(), warning, &mut state_guard)
+  }
+
+  fn handle_retrying(&self, file: FileName, attempt: RetryAttempt, error: CopyError) {
+    let mut state_guard = self.state.lock().unwrap();
+    Self::log(format!("{} 🔁 retrying ({attempt}) after: {}", file.name(), error.error()), &mut state_guard)
+  }
+
+  fn handle_created_directory(&self, directory: FileName) {
+    let mut state_guard = self.state.lock().unwrap();
+    state_guard.created_directories += 1;
+    Self::log(format!("{}/ 📁 created", directory.name()), &mut state_guard)
+  }
+
+  fn handle_backed_up(&self, file: FileName, backup_file: PathBuf) {
+    let mut state_guard = self.state.lock().unwrap();
+    Self::log(format!("{} 💾 backed up to {}", file.name(), backup_file.to_string_lossy()), &mut state_guard)
+  }
+
+  fn handle_removed_extraneous(&self, file: FileName, trashed_file: Option<PathBuf>) {
+    let mut state_guard = self.state.lock().unwrap();
+    let removal =
+      match trashed_file {
+        Some(trashed_file) => format!("moved to {}", trashed_file.to_string_lossy()),
+        None => "deleted".to_owned()
+      };
+
+    Self::log(format!("{} 🗑️ {removal}", file.name()), &mut state_guard)
   }
 
   fn insert_completed_bar(arg: &str, file_size: FileSize, state: &mut MutexGuard<State>) {
     Self::log(format!("{arg} ({file_size}) ✅"), state)
   }
 
+  fn insert_skipped_bar(arg: &str, file_size: FileSize, reason: SkipReason, state: &mut MutexGuard<State>) {
+    Self::log(format!("{arg} ({file_size}) ⏭️ skipped: {reason}"), state)
+  }
+
+  fn insert_warning_bar(arg: &str, warning: &str, state: &mut MutexGuard<State>) {
+    let errors = &mut state.errors;
+    let warning_string = format!("{arg} ({}) ⚠️", warning);
+    errors.push(warning_string.clone());
+    state.error_bar.clone().with_prefix(state.errors.join("\n"));
+    Self::log(warning_string, state)
+  }
+
   fn insert_failed_bar(arg: &str, error: &str, state: &mut MutexGuard<State>) {
     let errors = &mut state.errors;
     let error_string = format!("{arg} ({}) ❌", error);
@@ -216,7 +319,7 @@ impl OverallProgressMonitor {
       state_guard.completed_bytes += file_size.size()
     }
     self.set_progress(&state_guard);
-    Self::set_stats(&state_guard, &self.stats_bar, self.total_bytes, self.start_time.unwrap());
+    Self::set_stats(&state_guard, &self.stats_bar, self.total_bytes, self.start_time.unwrap(), &self.bandwidth_limiter);
 
     // If all items are completed, then finish
     if state_guard.completed >= self.items {
@@ -233,24 +336,30 @@ impl OverallProgressMonitor {
     drop(state_guard)
   }
 
+  fn handle_skipped_bytes(&self, bytes: u64) {
+    let mut state_guard = self.state.lock().unwrap();
+    state_guard.skipped_bytes += bytes;
+    drop(state_guard)
+  }
+
   fn set_progress(&self, state_guard: &MutexGuard<State>) {
     self.overall_bar.set_prefix(format!("completed:{}/{}", state_guard.completed, self.items));
   }
 
-  fn set_stats(state_guard: &MutexGuard<State>, pb: &ProgressBar, total_bytes: u64, start_time: Instant) {
+  fn set_stats(state_guard: &MutexGuard<State>, pb: &ProgressBar, total_bytes: u64, start_time: Instant, bandwidth_limiter: &BandwidthLimiter) {
     let elaped_time_seconds = start_time.elapsed().as_secs();
 
+    // Only bytes copied in this run count towards the speed
     let speed =
-      if elaped_time_seconds == 0 {
-        0
-      } else {
-        state_guard.inprogress_bytes / elaped_time_seconds
-      };
+      state_guard
+        .inprogress_bytes
+        .checked_div(elaped_time_seconds)
+        .unwrap_or(0);
 
-    let remaining_bytes = total_bytes - state_guard.inprogress_bytes;
+    let copied_bytes = state_guard.inprogress_bytes + state_guard.skipped_bytes;
+    let remaining_bytes = total_bytes.saturating_sub(copied_bytes);
     let estimated_completion_time =
-      if speed > 0 {
-        let seconds_remaining = remaining_bytes / speed; // bytes/second
+      if let Some(seconds_remaining) = remaining_bytes.checked_div(speed) { // bytes/second
         use chrono::prelude::*;
 
         let local_now = Local::now();
@@ -262,8 +371,7 @@ impl OverallProgressMonitor {
       };
 
       let duration =
-        if speed > 0 {
-          let seconds_remaining = remaining_bytes / speed; // bytes/second
+        if let Some(seconds_remaining) = remaining_bytes.checked_div(speed) { // bytes/second
           let current = Instant::now();
           let end_time = current.checked_add(Duration::from_secs(seconds_remaining)).unwrap();
 
@@ -282,13 +390,27 @@ impl OverallProgressMonitor {
           format!("{:^11}", "00h 00m 00s".to_owned())
         };
 
+    let directories =
+      match state_guard.created_directories {
+        0 => String::new(),
+        created => format!(" dirs:({created})")
+      };
+
+    let limit =
+      bandwidth_limiter
+        .limit()
+        .map(|limit| format!(" limit:({limit})"))
+        .unwrap_or_default();
+
     pb.set_prefix(
       format!(
-        "copied:{} files:({}/{}) speed:({}) done:({}) takes:({})",
-        size_pretty(state_guard.inprogress_bytes),
+        "copied:{} files:({}/{}){} speed:({}){} done:({}) takes:({})",
+        size_pretty(copied_bytes),
         size_pretty(state_guard.completed_bytes),
         size_pretty(total_bytes),
+        directories,
         size_pretty(speed),
+        limit,
         estimated_completion_time,
         duration
       )
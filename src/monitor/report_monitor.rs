@@ -0,0 +1,20 @@
+use tokio::sync::mpsc::Receiver;
+
+use crate::model::FileEvent;
+use crate::report::RunReport;
+
+/// Records the events of every file in a report, for --report.
+pub struct ReportMonitor;
+
+impl ReportMonitor {
+
+  /// This is a low cardinality event receiver, as progress is aggregated before it's sent.
+  /// Returns the report once all senders have been dropped.
+  pub async fn monitor(mut rx: Receiver<FileEvent>, mut report: RunReport) -> RunReport {
+    while let Some(event) = rx.recv().await {
+      report.record(&event)
+    }
+
+    report
+  }
+}
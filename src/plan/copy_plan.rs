@@ -0,0 +1,512 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::args::{CompareMethod, OverwritePolicy};
+use crate::copy::{file_checksum, BackupPolicy, EntryType, HardLinkId, SourceFile};
+use crate::model::SkipReason;
+use super::{find_extraneous_files, ExtraneousFile, PlanOptions};
+
+/// What to do with a single source file.
+#[derive(Debug, Clone)]
+pub enum CopyAction {
+  Copy,
+  /// Create the directory, if it doesn't exist, before any files are copied
+  CreateDirectory,
+  /// Create a symlink with this target
+  Symlink(PathBuf),
+  /// Create a hard link to this destination file, which is copied first
+  HardLink(PathBuf),
+  Skip(SkipReason),
+}
+
+/// What happens to a file that is already at the destination, as decided by --overwrite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExistingDestination {
+  /// Nothing is at the destination, or the entry isn't a file
+  Missing,
+  Overwritten,
+  /// The existing file is left alone and the source is skipped
+  Kept,
+  /// The existing file is left alone and the source is copied under a new name
+  Renamed,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlanEntry {
+  source_file: SourceFile,
+  destination: PathBuf,
+  action: CopyAction,
+  existing_destination: ExistingDestination,
+}
+
+impl PlanEntry {
+
+  pub fn source_file(&self) -> &SourceFile {
+    &self.source_file
+  }
+
+  /// Full path of the destination file
+  pub fn destination(&self) -> &Path {
+    self.destination.as_path()
+  }
+
+  pub fn action(&self) -> &CopyAction {
+    &self.action
+  }
+
+  pub fn existing_destination(&self) -> ExistingDestination {
+    self.existing_destination
+  }
+}
+
+/// The decisions made for every source file, before anything is written to the destination.
+#[derive(Debug, Clone)]
+pub struct CopyPlan {
+  entries: Vec<PlanEntry>,
+  /// Destination files to remove in mirror mode
+  extraneous_files: Vec<ExtraneousFile>,
+  trash_dir: Option<PathBuf>,
+}
+
+impl CopyPlan {
+
+  pub fn new<D: AsRef<Path>>(source_files: Vec<SourceFile>, destination_dir: D, options: &PlanOptions) -> Self {
+    // The first copied destination of each set of hard linked source files
+    let mut linked_destinations: HashMap<HardLinkId, PathBuf> = HashMap::new();
+    // Renamed files must not take the name of any other file being copied
+    let mut claimed_destinations: HashSet<PathBuf> =
+      source_files
+        .iter()
+        .map(|source_file| destination_dir.as_ref().join(source_file.relative_path()))
+        .collect();
+
+    let entries =
+      source_files
+        .into_iter()
+        .map(|source_file| {
+          let destination = destination_dir.as_ref().join(source_file.relative_path());
+          let (destination, action, existing_destination) = Self::decide_action(&source_file, destination, options, &mut claimed_destinations);
+
+          // Hard linked files are decided like any other file, but only the first one that is copied has its data copied.
+          // The others that are copied link to it, while skipped ones keep whatever is at their destination.
+          let action =
+            match (source_file.hard_link_id(), action) {
+              (Some(id), CopyAction::Copy) =>
+                match linked_destinations.get(&id) {
+                  Some(linked_destination) => CopyAction::HardLink(linked_destination.clone()),
+                  None => {
+                    linked_destinations.insert(id, destination.clone());
+                    CopyAction::Copy
+                  }
+                },
+              (_, action) => action
+            };
+
+          PlanEntry {
+            source_file,
+            destination,
+            action,
+            existing_destination,
+          }
+        })
+        .collect::<Vec<_>>();
+
+    let extraneous_files =
+      if options.mirror() {
+        // Renamed files are copied next to the existing file, which --overwrite rename keeps
+        let kept_destinations: Vec<PathBuf> =
+          entries
+            .iter()
+            .filter(|e| e.existing_destination() == ExistingDestination::Renamed)
+            .map(|e| destination_dir.as_ref().join(e.source_file().relative_path()))
+            .collect();
+
+        let planned_destinations: HashSet<&Path> =
+          entries
+            .iter()
+            .map(|e| e.destination())
+            .chain(kept_destinations.iter().map(PathBuf::as_path))
+            .collect();
+        let excluded_dirs: Vec<&Path> = options.trash_dir().into_iter().chain(options.backup().and_then(BackupPolicy::backup_dir)).collect();
+        find_extraneous_files(destination_dir.as_ref(), &planned_destinations, options.ignored(), &excluded_dirs, options.backup())
+      } else {
+        vec![]
+      };
+
+    Self {
+      entries,
+      extraneous_files,
+      trash_dir: options.trash_dir().map(Path::to_path_buf),
+    }
+  }
+
+  pub fn entries(&self) -> &[PlanEntry] {
+    &self.entries
+  }
+
+  pub fn into_entries(self) -> Vec<PlanEntry> {
+    self.entries
+  }
+
+  pub fn extraneous_files(&self) -> &[ExtraneousFile] {
+    &self.extraneous_files
+  }
+
+  pub fn trash_dir(&self) -> Option<&Path> {
+    self.trash_dir.as_deref()
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Number of entries that aren't directories
+  pub fn num_files(&self) -> usize {
+    self
+      .entries
+      .iter()
+      .filter(|e| !matches!(e.action, CopyAction::CreateDirectory))
+      .count()
+  }
+
+  /// Size of all source files, including those that will be skipped.
+  /// Hard linked files are only counted once.
+  pub fn total_bytes(&self) -> u64 {
+    self
+      .entries
+      .iter()
+      .filter(|e| !matches!(e.action, CopyAction::HardLink(_)))
+      .map(|e| e.source_file.size())
+      .sum()
+  }
+
+  pub fn hard_links(&self) -> impl Iterator<Item = &PlanEntry> {
+    self
+      .entries
+      .iter()
+      .filter(|e| matches!(e.action, CopyAction::HardLink(_)))
+  }
+
+  /// Source and destination paths of every directory under the source root,
+  /// ordered so that subdirectories come before their parents.
+  pub fn directories(&self) -> Vec<(PathBuf, PathBuf)> {
+    let mut directories: Vec<(PathBuf, PathBuf)> =
+      self
+        .entries
+        .iter()
+        .filter(|e| matches!(e.action, CopyAction::CreateDirectory))
+        .map(|e| (e.source_file.full_path().to_path_buf(), e.destination.clone()))
+        .collect();
+
+    directories.sort_by_key(|(_, destination)| std::cmp::Reverse(destination.components().count()));
+    directories
+  }
+
+  pub fn skipped(&self) -> impl Iterator<Item = &PlanEntry> {
+    self
+      .entries
+      .iter()
+      .filter(|e| matches!(e.action, CopyAction::Skip(_)))
+  }
+
+  /// Files that already exist at the destination, grouped by what --overwrite does with them
+  pub fn existing_destinations(&self, existing_destination: ExistingDestination) -> impl Iterator<Item = &PlanEntry> {
+    self
+      .entries
+      .iter()
+      .filter(move |e| e.existing_destination == existing_destination)
+  }
+
+  fn decide_action(source_file: &SourceFile, destination: PathBuf, options: &PlanOptions, claimed_destinations: &mut HashSet<PathBuf>) -> (PathBuf, CopyAction, ExistingDestination) {
+    match source_file.entry_type() {
+      EntryType::File => Self::decide_file_action(source_file, destination, options, claimed_destinations),
+      EntryType::Directory => (destination, CopyAction::CreateDirectory, ExistingDestination::Missing),
+      EntryType::Symlink(target) => (destination, CopyAction::Symlink(target.clone()), ExistingDestination::Missing),
+      EntryType::Skipped(reason) => (destination, CopyAction::Skip(reason.clone()), ExistingDestination::Missing),
+    }
+  }
+
+  fn decide_file_action(source_file: &SourceFile, destination: PathBuf, options: &PlanOptions, claimed_destinations: &mut HashSet<PathBuf>) -> (PathBuf, CopyAction, ExistingDestination) {
+    if fs::symlink_metadata(&destination).is_err() {
+      return (destination, CopyAction::Copy, ExistingDestination::Missing)
+    }
+
+    if let Some(compare_method) = options.incremental() {
+      if Self::is_up_to_date(source_file, &destination, compare_method, options) {
+        return (destination, CopyAction::Skip(Self::up_to_date_reason(compare_method)), ExistingDestination::Kept)
+      }
+    }
+
+    match options.overwrite() {
+      OverwritePolicy::Always => (destination, CopyAction::Copy, ExistingDestination::Overwritten),
+
+      OverwritePolicy::Never => (destination, CopyAction::Skip(SkipReason::DestinationExists), ExistingDestination::Kept),
+
+      OverwritePolicy::IfNewer => {
+        if Self::is_newer(source_file, &destination) {
+          (destination, CopyAction::Copy, ExistingDestination::Overwritten)
+        } else {
+          (destination, CopyAction::Skip(SkipReason::DestinationNotOlder), ExistingDestination::Kept)
+        }
+      },
+
+      OverwritePolicy::IfDifferent => {
+        let compare_method = options.compare();
+        if Self::is_up_to_date(source_file, &destination, compare_method, options) {
+          (destination, CopyAction::Skip(Self::up_to_date_reason(compare_method)), ExistingDestination::Kept)
+        } else {
+          (destination, CopyAction::Copy, ExistingDestination::Overwritten)
+        }
+      },
+
+      OverwritePolicy::Rename => {
+        let renamed = Self::unused_destination(&destination, claimed_destinations);
+        claimed_destinations.insert(renamed.clone());
+        (renamed, CopyAction::Copy, ExistingDestination::Renamed)
+      },
+    }
+  }
+
+  fn up_to_date_reason(compare_method: CompareMethod) -> SkipReason {
+    match compare_method {
+      CompareMethod::SizeMtime => SkipReason::SameSizeAndModifiedTime,
+      CompareMethod::Checksum => SkipReason::SameChecksum,
+    }
+  }
+
+  /// Whether the source file was modified after the destination file
+  fn is_newer(source_file: &SourceFile, destination: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified());
+
+    match (modified(source_file.full_path()), modified(destination)) {
+      (Ok(source_modified), Ok(destination_modified)) => source_modified > destination_modified,
+      _ => false
+    }
+  }
+
+  /// The first of "name (1).ext", "name (2).ext", ... that is neither on disk nor claimed by another file
+  fn unused_destination(destination: &Path, claimed_destinations: &HashSet<PathBuf>) -> PathBuf {
+    let stem = destination.file_stem().unwrap_or_default().to_string_lossy();
+    let extension =
+      destination
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1..)
+      .map(|n| destination.with_file_name(format!("{stem} ({n}){extension}")))
+      .find(|candidate| !claimed_destinations.contains(candidate) && fs::symlink_metadata(candidate).is_err())
+      .expect("there is always an unused file name")
+  }
+
+  fn is_up_to_date(source_file: &SourceFile, destination: &Path, compare_method: CompareMethod, options: &PlanOptions) -> bool {
+    let (Ok(source_meta), Ok(destination_meta)) = (fs::metadata(source_file.full_path()), fs::metadata(destination)) else {
+      return false
+    };
+
+    if !destination_meta.is_file() || source_meta.len() != destination_meta.len() {
+      return false
+    }
+
+    match compare_method {
+      CompareMethod::SizeMtime => {
+        // Copied files get a new modification time, so a destination that is at least as new as its source is up to date
+        match (source_meta.modified(), destination_meta.modified()) {
+          (Ok(source_modified), Ok(destination_modified)) => destination_modified >= source_modified,
+          _ => false
+        }
+      },
+
+      CompareMethod::Checksum => {
+        match (file_checksum(source_file.full_path(), options.checksum()), file_checksum(destination, options.checksum())) {
+          (Ok(source_checksum), Ok(destination_checksum)) => source_checksum == destination_checksum,
+          _ => false
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use pretty_assertions::assert_eq;
+    use crate::cli::Args;
+    use crate::args::{ExternalSymlinkPolicy, SymlinkMode};
+
+    fn plan(source: &Path, destination: &Path, overwrite: &str) -> CopyPlan {
+      let args = Args::parse_from(["coop", "-s", &source.to_string_lossy(), "-d", &destination.to_string_lossy(), "--overwrite", overwrite]);
+      let source_files = SourceFile::get_source_files(&source.to_path_buf(), &[], SymlinkMode::Recreate, ExternalSymlinkPolicy::Allow);
+      CopyPlan::new(source_files, destination, &PlanOptions::from(&args))
+    }
+
+    #[test]
+    fn renames_to_the_first_unused_name() {
+      let destination = tempfile::tempdir().unwrap();
+      std::fs::write(destination.path().join("report (1).txt"), "taken").unwrap();
+      let claimed = HashSet::from([destination.path().join("report (2).txt")]);
+
+      assert_eq!(
+        CopyPlan::unused_destination(&destination.path().join("report.txt"), &claimed),
+        destination.path().join("report (3).txt")
+      )
+    }
+
+    #[test]
+    fn renames_files_without_an_extension() {
+      let destination = tempfile::tempdir().unwrap();
+
+      assert_eq!(
+        CopyPlan::unused_destination(&destination.path().join("Makefile"), &HashSet::new()),
+        destination.path().join("Makefile (1)")
+      )
+    }
+
+    #[test]
+    fn keeps_existing_files_when_never_overwriting() {
+      let source = tempfile::tempdir().unwrap();
+      let destination = tempfile::tempdir().unwrap();
+      std::fs::write(source.path().join("existing.txt"), "new").unwrap();
+      std::fs::write(source.path().join("missing.txt"), "new").unwrap();
+      std::fs::write(destination.path().join("existing.txt"), "old").unwrap();
+
+      let plan = plan(source.path(), destination.path(), "never");
+      let mut decisions: Vec<(String, ExistingDestination, bool)> =
+        plan
+          .entries()
+          .iter()
+          .map(|e| (e.source_file().relative_path(), e.existing_destination(), matches!(e.action(), CopyAction::Skip(SkipReason::DestinationExists))))
+          .collect();
+      decisions.sort_by(|a, b| a.0.cmp(&b.0));
+
+      assert_eq!(
+        decisions,
+        vec![
+          ("existing.txt".to_owned(), ExistingDestination::Kept, true),
+          ("missing.txt".to_owned(), ExistingDestination::Missing, false)
+        ]
+      )
+    }
+
+    #[test]
+    fn renamed_files_do_not_take_the_name_of_another_source_file() {
+      let source = tempfile::tempdir().unwrap();
+      let destination = tempfile::tempdir().unwrap();
+      std::fs::write(source.path().join("a.txt"), "new").unwrap();
+      std::fs::write(source.path().join("a (1).txt"), "new").unwrap();
+      std::fs::write(destination.path().join("a.txt"), "old").unwrap();
+
+      let plan = plan(source.path(), destination.path(), "rename");
+      let renamed: Vec<&Path> =
+        plan
+          .existing_destinations(ExistingDestination::Renamed)
+          .map(|e| e.destination())
+          .collect();
+
+      assert_eq!(renamed, vec![destination.path().join("a (2).txt").as_path()])
+    }
+
+    #[test]
+    fn hard_links_only_to_copied_files() {
+      let source = tempfile::tempdir().unwrap();
+      let destination = tempfile::tempdir().unwrap();
+      std::fs::write(source.path().join("a"), "linked").unwrap();
+      std::fs::hard_link(source.path().join("a"), source.path().join("b")).unwrap();
+      std::fs::write(destination.path().join("b"), "KEEP").unwrap();
+
+      let plan = plan(source.path(), destination.path(), "never");
+      let mut actions: Vec<(String, String)> =
+        plan
+          .entries()
+          .iter()
+          .map(|e| (e.source_file().relative_path(), format!("{:?}", e.action())))
+          .collect();
+      actions.sort();
+
+      assert_eq!(
+        actions,
+        vec![
+          ("a".to_owned(), "Copy".to_owned()),
+          ("b".to_owned(), "Skip(DestinationExists)".to_owned())
+        ]
+      )
+    }
+
+    #[test]
+    fn does_not_delete_files_kept_by_renaming() {
+      let source = tempfile::tempdir().unwrap();
+      let destination = tempfile::tempdir().unwrap();
+      std::fs::write(source.path().join("a.txt"), "new").unwrap();
+      std::fs::write(destination.path().join("a.txt"), "old").unwrap();
+      std::fs::write(destination.path().join("stale.txt"), "old").unwrap();
+
+      let args = Args::parse_from(["coop", "-s", &source.path().to_string_lossy(), "-d", &destination.path().to_string_lossy(), "--overwrite", "rename", "--delete"]);
+      let source_files = SourceFile::get_source_files(&source.path().to_path_buf(), &[], SymlinkMode::Recreate, ExternalSymlinkPolicy::Allow);
+      let plan = CopyPlan::new(source_files, destination.path(), &PlanOptions::from(&args));
+      let extraneous: Vec<&str> = plan.extraneous_files().iter().map(|f| f.relative_path()).collect();
+
+      assert_eq!(extraneous, vec!["stale.txt"])
+    }
+
+    #[test]
+    fn dry_runs_plan_the_same_checksum_comparisons_as_the_copy() {
+      let source = tempfile::tempdir().unwrap();
+      let destination = tempfile::tempdir().unwrap();
+      // Same size and newer, but different
+      std::fs::write(source.path().join("changed.txt"), "new").unwrap();
+      std::fs::write(destination.path().join("changed.txt"), "old").unwrap();
+      // The same, but older
+      std::fs::write(source.path().join("same.txt"), "same").unwrap();
+      std::fs::write(destination.path().join("same.txt"), "same").unwrap();
+      filetime::set_file_mtime(destination.path().join("same.txt"), filetime::FileTime::from_unix_time(0, 0)).unwrap();
+
+      let source_arg = source.path().to_string_lossy();
+      let destination_arg = destination.path().to_string_lossy();
+      let actions = |options: &[&str]| -> Vec<(String, String)> {
+        let args = Args::parse_from([vec!["coop", "-s", &source_arg, "-d", &destination_arg, "--compare", "checksum"], options.to_vec()].concat());
+        let source_files = SourceFile::get_source_files(&source.path().to_path_buf(), &[], SymlinkMode::Recreate, ExternalSymlinkPolicy::Allow);
+        let plan = CopyPlan::new(source_files, destination.path(), &PlanOptions::from(&args));
+        let mut actions: Vec<(String, String)> =
+          plan
+            .entries()
+            .iter()
+            .map(|e| (e.source_file().relative_path(), format!("{:?}", e.action())))
+            .collect();
+        actions.sort();
+        actions
+      };
+
+      for options in [vec!["--incremental"], vec!["--overwrite", "if-different"]] {
+        let planned = actions(&options);
+
+        assert_eq!(
+          planned,
+          vec![
+            ("changed.txt".to_owned(), "Copy".to_owned()),
+            ("same.txt".to_owned(), "Skip(SameChecksum)".to_owned())
+          ]
+        );
+        assert_eq!(actions(&[options, vec!["--dry-run"]].concat()), planned)
+      }
+    }
+
+    #[test]
+    fn plans_every_directory_with_subdirectories_first() {
+      let source = tempfile::tempdir().unwrap();
+      let destination = tempfile::tempdir().unwrap();
+      std::fs::create_dir_all(source.path().join("app").join("logs")).unwrap();
+
+      let plan = plan(source.path(), destination.path(), "always");
+
+      assert_eq!(plan.num_files(), 0);
+      assert_eq!(
+        plan.directories(),
+        vec![
+          (source.path().join("app").join("logs"), destination.path().join("app").join("logs")),
+          (source.path().join("app"), destination.path().join("app"))
+        ]
+      )
+    }
+}
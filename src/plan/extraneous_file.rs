@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use walkdir::WalkDir;
+
+use crate::copy::BackupPolicy;
+
+/// A destination file with no source file, which is removed in mirror mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtraneousFile {
+  path: PathBuf,
+  relative: String,
+  size: u64,
+}
+
+impl ExtraneousFile {
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Path relative to the destination directory
+  pub fn relative_path(&self) -> &str {
+    &self.relative
+  }
+
+  pub fn size(&self) -> u64 {
+    self.size
+  }
+}
+
+/// Finds the files under `destination_dir` that aren't in `planned_destinations`.
+///
+/// Files matching the ignore patterns are left alone, as are partial files kept for --resume, backups kept next to
+/// their destination files and anything in the excluded directories, such as the trash and backup directories.
+pub fn find_extraneous_files(destination_dir: &Path, planned_destinations: &HashSet<&Path>, ignored_regexes: &[Regex], excluded_dirs: &[&Path], backup: Option<&BackupPolicy>) -> Vec<ExtraneousFile> {
+  WalkDir::new(destination_dir)
+    .follow_links(false)
+    .into_iter()
+    .filter_entry(|de| !excluded_dirs.iter().any(|excluded| de.path().starts_with(excluded)))
+    .filter_map(|de| de.ok())
+    .filter(|de| !de.file_type().is_dir())
+    .filter(|de| !planned_destinations.contains(de.path()))
+    .filter(|de| !is_partial_file(de.path()))
+    .filter(|de| !backup.is_some_and(|backup| backup.is_backup(de.path())))
+    .filter(|de| !ignored_regexes.iter().any(|r| r.is_match(de.path().to_string_lossy().as_ref())))
+    .filter_map(|de| {
+      let relative = de.path().strip_prefix(destination_dir).ok()?.to_string_lossy().to_string();
+      let size = de.metadata().map(|m| m.len()).unwrap_or(0);
+
+      Some(
+        ExtraneousFile {
+          path: de.path().to_path_buf(),
+          relative,
+          size
+        }
+      )
+    })
+    .collect()
+}
+
+fn is_partial_file(path: &Path) -> bool {
+  path
+    .file_name()
+    .map(|name| {
+      let name = name.to_string_lossy();
+      name.starts_with('.') && name.ends_with(".coop-partial")
+    })
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn finds_files_without_a_source() {
+      let destination = tempfile::tempdir().unwrap();
+      std::fs::create_dir_all(destination.path().join("old")).unwrap();
+      std::fs::write(destination.path().join("kept.txt"), "kept").unwrap();
+      std::fs::write(destination.path().join("old").join("stale.txt"), "stale").unwrap();
+      std::fs::write(destination.path().join(".git"), "ignored").unwrap();
+      std::fs::write(destination.path().join(".kept.txt.coop-partial"), "partial").unwrap();
+
+      let kept = destination.path().join("kept.txt");
+      let planned = HashSet::from([kept.as_path()]);
+      let ignored = [Regex::new(".git").unwrap()];
+
+      let extraneous: Vec<String> =
+        find_extraneous_files(destination.path(), &planned, &ignored, &[], None)
+          .iter()
+          .map(|f| f.relative_path().to_owned())
+          .collect();
+
+      assert_eq!(extraneous, vec![Path::new("old").join("stale.txt").to_string_lossy().to_string()])
+    }
+
+    #[test]
+    fn leaves_the_trash_directory_alone() {
+      let destination = tempfile::tempdir().unwrap();
+      let trash = destination.path().join("trash");
+      std::fs::create_dir_all(&trash).unwrap();
+      std::fs::write(trash.join("deleted.txt"), "deleted").unwrap();
+
+      assert_eq!(find_extraneous_files(destination.path(), &HashSet::new(), &[], &[trash.as_path()], None), vec![])
+    }
+}
@@ -0,0 +1,7 @@
+mod copy_plan;
+mod extraneous_file;
+mod plan_options;
+
+pub use copy_plan::{CopyAction, CopyPlan, ExistingDestination, PlanEntry};
+pub use extraneous_file::{find_extraneous_files, ExtraneousFile};
+pub use plan_options::PlanOptions;
@@ -0,0 +1,75 @@
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::args::{ChecksumAlgorithm, CompareMethod, OverwritePolicy};
+use crate::cli::Args;
+use crate::copy::BackupPolicy;
+
+/// Settings that decide what happens to each source file before any copying starts.
+#[derive(Debug, Clone)]
+pub struct PlanOptions {
+  incremental: Option<CompareMethod>,
+  compare: CompareMethod,
+  overwrite: OverwritePolicy,
+  checksum: ChecksumAlgorithm,
+  mirror: bool,
+  ignored: Vec<Regex>,
+  trash_dir: Option<PathBuf>,
+  backup: Option<BackupPolicy>,
+}
+
+impl PlanOptions {
+
+  /// The comparison used to skip up to date files, if running in incremental mode.
+  pub fn incremental(&self) -> Option<CompareMethod> {
+    self.incremental
+  }
+
+  /// How source files are compared against destination files, by --overwrite if-different
+  pub fn compare(&self) -> CompareMethod {
+    self.compare
+  }
+
+  pub fn overwrite(&self) -> OverwritePolicy {
+    self.overwrite
+  }
+
+  pub fn checksum(&self) -> ChecksumAlgorithm {
+    self.checksum
+  }
+
+  /// Whether destination files without a source file are removed
+  pub fn mirror(&self) -> bool {
+    self.mirror
+  }
+
+  pub fn ignored(&self) -> &[Regex] {
+    &self.ignored
+  }
+
+  /// Where removed destination files are moved to, instead of being deleted
+  pub fn trash_dir(&self) -> Option<&Path> {
+    self.trash_dir.as_deref()
+  }
+
+  /// How replaced destination files are backed up, which mirror mode leaves alone
+  pub fn backup(&self) -> Option<&BackupPolicy> {
+    self.backup.as_ref()
+  }
+}
+
+impl From<&Args> for PlanOptions {
+  fn from(args: &Args) -> Self {
+    Self {
+      incremental: args.incremental.then_some(args.compare),
+      compare: args.compare,
+      overwrite: args.overwrite,
+      checksum: args.checksum,
+      mirror: args.delete,
+      ignored: args.ignore.clone(),
+      trash_dir: args.trash_dir.clone(),
+      backup: BackupPolicy::new(args.backup, &args.backup_suffix, args.backup_dir.clone(), args.destination_dir.clone(), chrono::Local::now()),
+    }
+  }
+}
@@ -61,6 +61,11 @@ impl MyProgressBar {
     self.clear()
   }
 
+  /// Shows a problem that doesn't stop the copy.
+  pub fn set_warning(&self, msg: &str) {
+    self.error.set_message(msg.to_owned())
+  }
+
   pub fn set_status(&self, msg: &str) {
     self.secondary.set_message(msg.to_owned())
   }
@@ -73,6 +78,12 @@ impl MyProgressBar {
     self.primary.inc(bytes_written)
   }
 
+  /// Starts the progress from `bytes_written` instead of zero, for resumed copies.
+  pub fn resume_from(&self, bytes_written: u64) {
+    self.primary.set_position(bytes_written);
+    self.primary.reset_eta()
+  }
+
   pub fn complete(&self, msg: &str) {
     self.secondary.set_message(msg.to_owned());
   }
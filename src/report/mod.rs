@@ -0,0 +1,3 @@
+mod run_report;
+
+pub use run_report::RunReport;
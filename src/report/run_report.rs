@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::args::{ChecksumAlgorithm, ReportFormat};
+use crate::model::{FileEvent, FileEventKind};
+
+/// Every file of a run and what happened to it, built from the events of its copies, for --report.
+#[derive(Debug, Serialize)]
+pub struct RunReport {
+  started: DateTime<Local>,
+  finished: Option<DateTime<Local>>,
+  duration_secs: Option<f64>,
+  /// The algorithm of the files' checksums, when they were verified with one
+  checksum_algorithm: Option<String>,
+  totals: ReportTotals,
+  files: Vec<FileReport>,
+  /// Where each path is in `files`
+  #[serde(skip)]
+  positions: HashMap<String, usize>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ReportTotals {
+  files: u64,
+  succeeded: u64,
+  skipped: u64,
+  failed: u64,
+  /// The combined size of every file
+  bytes: u64,
+  /// Bytes written in this run, which leaves out skipped files and resumed parts of files
+  bytes_copied: u64,
+  throughput_bytes_per_sec: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct FileReport {
+  /// Relative to the source directory
+  path: String,
+  size: u64,
+  status: FileReportStatus,
+  /// Why the file was skipped or failed
+  reason: Option<String>,
+  started: Option<DateTime<Local>>,
+  finished: Option<DateTime<Local>>,
+  duration_secs: Option<f64>,
+  bytes_copied: u64,
+  throughput_bytes_per_sec: Option<f64>,
+  checksum: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum FileReportStatus {
+  /// Never finished, such as when the run was interrupted
+  Pending,
+  Succeeded,
+  Skipped,
+  Failed,
+}
+
+impl FileReportStatus {
+  fn name(&self) -> &'static str {
+    match self {
+      FileReportStatus::Pending => "pending",
+      FileReportStatus::Succeeded => "succeeded",
+      FileReportStatus::Skipped => "skipped",
+      FileReportStatus::Failed => "failed",
+    }
+  }
+}
+
+impl RunReport {
+
+  /// A report of the files in `files`, given as their relative paths and sizes, that are all still to be copied.
+  pub fn new<I: IntoIterator<Item = (String, u64)>>(files: I, checksum_algorithm: Option<ChecksumAlgorithm>, started: DateTime<Local>) -> Self {
+    let files: Vec<FileReport> =
+      files
+        .into_iter()
+        .map(|(path, size)| FileReport::new(path, size))
+        .collect();
+
+    let positions =
+      files
+        .iter()
+        .enumerate()
+        .map(|(position, file)| (file.path.clone(), position))
+        .collect();
+
+    Self {
+      started,
+      finished: None,
+      duration_secs: None,
+      checksum_algorithm: checksum_algorithm.and_then(|a| a.to_possible_value()).map(|v| v.get_name().to_owned()),
+      totals: ReportTotals::default(),
+      files,
+      positions,
+    }
+  }
+
+  /// Updates the file the event is for. Events for directories and extraneous files are ignored.
+  pub fn record(&mut self, event: &FileEvent) {
+    if let Some(file) = self.positions.get(event.path()).and_then(|position| self.files.get_mut(*position)) {
+      file.record(event)
+    }
+  }
+
+  /// Works out the durations, throughputs and totals, once no more events will be recorded.
+  pub fn finish(&mut self, finished: DateTime<Local>) {
+    let mut totals = ReportTotals::default();
+
+    for file in &mut self.files {
+      file.finish();
+
+      totals.files += 1;
+      totals.bytes += file.size;
+      totals.bytes_copied += file.bytes_copied;
+      match file.status {
+        FileReportStatus::Succeeded => totals.succeeded += 1,
+        FileReportStatus::Skipped => totals.skipped += 1,
+        FileReportStatus::Failed => totals.failed += 1,
+        FileReportStatus::Pending => (),
+      }
+    }
+
+    let duration_secs = seconds_between(self.started, finished);
+    totals.throughput_bytes_per_sec = throughput(totals.bytes_copied, duration_secs);
+
+    self.finished = Some(finished);
+    self.duration_secs = Some(duration_secs);
+    self.totals = totals;
+  }
+
+  pub fn write<W: Write>(&self, mut writer: W, format: ReportFormat) -> std::io::Result<()> {
+    match format {
+      ReportFormat::Json => {
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writeln!(writer)?
+      },
+      ReportFormat::Csv => self.write_csv(&mut writer)?
+    }
+
+    writer.flush()
+  }
+
+  /// One row per file, then a row of totals with a status of "total"
+  fn write_csv<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+    writeln!(writer, "path,size,status,reason,started,finished,duration_secs,bytes_copied,throughput_bytes_per_sec,checksum")?;
+
+    for file in &self.files {
+      let row = [
+        csv_field(&file.path),
+        file.size.to_string(),
+        file.status.name().to_owned(),
+        csv_field(file.reason.as_deref().unwrap_or_default()),
+        file.started.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        file.finished.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        optional(file.duration_secs),
+        file.bytes_copied.to_string(),
+        optional(file.throughput_bytes_per_sec),
+        file.checksum.clone().unwrap_or_default(),
+      ];
+      writeln!(writer, "{}", row.join(","))?
+    }
+
+    let totals = &self.totals;
+    let row = [
+      String::new(),
+      totals.bytes.to_string(),
+      "total".to_owned(),
+      format!("{} succeeded; {} skipped; {} failed", totals.succeeded, totals.skipped, totals.failed),
+      self.started.to_rfc3339(),
+      self.finished.map(|t| t.to_rfc3339()).unwrap_or_default(),
+      optional(self.duration_secs),
+      totals.bytes_copied.to_string(),
+      optional(totals.throughput_bytes_per_sec),
+      String::new(),
+    ];
+    writeln!(writer, "{}", row.join(","))
+  }
+}
+
+impl FileReport {
+  fn new(path: String, size: u64) -> Self {
+    Self {
+      path,
+      size,
+      status: FileReportStatus::Pending,
+      reason: None,
+      started: None,
+      finished: None,
+      duration_secs: None,
+      bytes_copied: 0,
+      throughput_bytes_per_sec: None,
+      checksum: None,
+    }
+  }
+
+  fn record(&mut self, event: &FileEvent) {
+    // A file starts with its first event, which is its only one when it's skipped
+    self.started.get_or_insert(event.timestamp());
+
+    match event.kind() {
+      FileEventKind::Progress { copied, .. } => self.bytes_copied = *copied,
+      FileEventKind::ChecksumsMatch { checksum } => self.checksum = Some(checksum.clone()),
+      FileEventKind::Success { .. } => self.finish_with(FileReportStatus::Succeeded, None, event),
+      FileEventKind::Skipped { reason, .. } => self.finish_with(FileReportStatus::Skipped, Some(reason.clone()), event),
+      FileEventKind::Failed { error, .. } => self.finish_with(FileReportStatus::Failed, Some(error.clone()), event),
+      _ => ()
+    }
+  }
+
+  fn finish_with(&mut self, status: FileReportStatus, reason: Option<String>, event: &FileEvent) {
+    self.status = status;
+    self.reason = reason;
+    self.finished = Some(event.timestamp());
+  }
+
+  fn finish(&mut self) {
+    if let (Some(started), Some(finished)) = (self.started, self.finished) {
+      let duration_secs = seconds_between(started, finished);
+      self.duration_secs = Some(duration_secs);
+      self.throughput_bytes_per_sec = throughput(self.bytes_copied, duration_secs);
+    }
+  }
+}
+
+fn seconds_between(started: DateTime<Local>, finished: DateTime<Local>) -> f64 {
+  (finished - started).num_microseconds().unwrap_or_default() as f64 / 1_000_000.0
+}
+
+/// Left out when nothing was copied, or it took no measurable time
+fn throughput(bytes: u64, duration_secs: f64) -> Option<f64> {
+  (bytes > 0 && duration_secs > 0.0).then(|| bytes as f64 / duration_secs)
+}
+
+fn optional(value: Option<f64>) -> String {
+  value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Quotes a field that contains a separator, quote or line break, doubling any quotes in it
+fn csv_field(value: &str) -> String {
+  if value.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value.to_owned()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use pretty_assertions::assert_eq;
+
+    fn report() -> RunReport {
+      let started = Local::now();
+      let at = |millis| started + TimeDelta::milliseconds(millis);
+
+      let mut report = RunReport::new([("dir/copied.bin".to_owned(), 2000), ("skipped, \"quoted\".txt".to_owned(), 10), ("failed.txt".to_owned(), 5)], Some(ChecksumAlgorithm::Blake3), started);
+
+      report.record(&FileEvent::at("dir/copied.bin", FileEventKind::NotStarted, at(0)));
+      report.record(&FileEvent::at("dir/copied.bin", FileEventKind::Progress { bytes: 2000, copied: 2000 }, at(100)));
+      report.record(&FileEvent::at("dir/copied.bin", FileEventKind::ChecksumsMatch { checksum: "abc123".to_owned() }, at(200)));
+      report.record(&FileEvent::at("dir/copied.bin", FileEventKind::Success { bytes: 2000 }, at(500)));
+      report.record(&FileEvent::at("skipped, \"quoted\".txt", FileEventKind::Skipped { bytes: 10, reason: "destination already exists".to_owned() }, at(0)));
+      report.record(&FileEvent::at("failed.txt", FileEventKind::NotStarted, at(0)));
+      report.record(&FileEvent::at("failed.txt", FileEventKind::Failed { reason: "read_failed", error: "Read failed: Input/output error".to_owned() }, at(250)));
+      report.record(&FileEvent::at("dir", FileEventKind::CreatedDirectory, at(0)));
+      report.finish(at(1000));
+
+      report
+    }
+
+    #[test]
+    fn reports_every_file_with_totals_as_json() {
+      let mut json = Vec::new();
+      report().write(&mut json, ReportFormat::Json).unwrap();
+      let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+
+      assert_eq!(json["checksum_algorithm"], "blake3");
+      assert_eq!(json["duration_secs"], 1.0);
+      assert_eq!(
+        json["totals"],
+        serde_json::json!({ "files": 3, "succeeded": 1, "skipped": 1, "failed": 1, "bytes": 2015, "bytes_copied": 2000, "throughput_bytes_per_sec": 2000.0 })
+      );
+
+      let copied = &json["files"][0];
+      assert_eq!(copied["path"], "dir/copied.bin");
+      assert_eq!(copied["status"], "succeeded");
+      assert_eq!(copied["duration_secs"], 0.5);
+      assert_eq!(copied["throughput_bytes_per_sec"], 4000.0);
+      assert_eq!(copied["checksum"], "abc123");
+
+      let failed = &json["files"][2];
+      assert_eq!(failed["status"], "failed");
+      assert_eq!(failed["reason"], "Read failed: Input/output error");
+      assert_eq!(failed["throughput_bytes_per_sec"], serde_json::Value::Null)
+    }
+
+    #[test]
+    fn reports_every_file_with_totals_as_csv() {
+      let mut csv = Vec::new();
+      report().write(&mut csv, ReportFormat::Csv).unwrap();
+      let csv = String::from_utf8(csv).unwrap();
+      let rows: Vec<&str> = csv.lines().collect();
+
+      assert_eq!(rows.len(), 5);
+      assert_eq!(rows[0], "path,size,status,reason,started,finished,duration_secs,bytes_copied,throughput_bytes_per_sec,checksum");
+      assert!(rows[1].starts_with("dir/copied.bin,2000,succeeded,,"), "{}", rows[1]);
+      assert!(rows[1].ends_with(",0.5,2000,4000,abc123"), "{}", rows[1]);
+      assert!(rows[2].starts_with("\"skipped, \"\"quoted\"\".txt\",10,skipped,destination already exists,"), "{}", rows[2]);
+      assert!(rows[4].starts_with(",2015,total,1 succeeded; 1 skipped; 1 failed,"), "{}", rows[4]);
+      assert!(rows[4].ends_with(",1,2000,2000,"), "{}", rows[4])
+    }
+
+    #[test]
+    fn leaves_unfinished_files_pending() {
+      let mut report = RunReport::new([("file.txt".to_owned(), 5)], None, Local::now());
+      report.record(&FileEvent::new("file.txt", FileEventKind::NotStarted));
+      report.finish(Local::now());
+
+      assert_eq!(report.files[0].status, FileReportStatus::Pending);
+      assert_eq!(report.files[0].duration_secs, None);
+      assert_eq!(report.checksum_algorithm, None)
+    }
+}
@@ -1,17 +1,24 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 use std::cmp::{max, min};
 
+use chrono::Local;
+use console::Term;
 use indicatif::MultiProgress;
 use tokio::sync::mpsc::{self};
 use tokio::task::JoinSet;
 
-use crate::args::BufferSize;
+use crate::args::{OutputTarget, PreserveAttribute, ReportFormat};
 use crate::cli::Args;
 use crate::console::{CoopConsole, UserResult};
-use crate::copy::{FileCopy, SourceFile};
-use crate::model::{FileStatus, InProgress};
+use crate::copy::{copy_extended_attributes, preserve_attributes, remove_extraneous_file, CopyOptions, FileCopy, SourceFile};
+use crate::model::{CopyError, FileEvent, FileStatus, InProgress};
+use crate::plan::{CopyAction, CopyPlan, ExtraneousFile, PlanOptions};
+use crate::report::RunReport;
+use super::RunOutcome;
 use crate::monitor::{
-  FileInProgressMonitor, InProgressSender, LifecycleEventMonitor, LifecycleEventSender, MonitorMux, NumFiles, OverallProgressMonitor, OverallProgressSender, TotalFileSize
+  EventSender, EventStreamMonitor, FileInProgressMonitor, InProgressSender, LifecycleEventMonitor, LifecycleEventSender, MonitorMux, NumFiles, OverallProgressMonitor, OverallProgressSender, ReportMonitor, TotalFileSize
 };
 
 pub struct CoopWorkflow {
@@ -26,45 +33,121 @@ impl CoopWorkflow {
     }
   }
 
-  pub async fn run(self) {
+  pub async fn run(self) -> RunOutcome {
     let args = self.args;
     let source = &args.source;
     let destination_dir = &args.destination_dir;
     let ignored_regexes = &args.ignore;
     let concurrency = args.concurrency;
-    let buffer_size = args.buffer_size.unwrap_or(BufferSize::DEFAULT_BUFFER_SIZE);
+    let copy_options = CopyOptions::from(&args);
     let skip_verification = args.skip_verify;
+    // Keep stdout for the event stream or report, if either is written there
+    let console_output = if args.stdout_taken() { Term::stderr() } else { Term::stdout() };
 
-    let files_to_copy = SourceFile::get_source_files(source, ignored_regexes);
+    if let Err(e) = std::fs::metadata(source) {
+      eprintln!("Could not read source {}: {e}", source.to_string_lossy());
+      return RunOutcome::PlanningFailed
+    }
+
+    let files_to_copy = SourceFile::get_source_files(source, ignored_regexes, args.symlinks, args.external_symlinks);
+    let copy_plan = CopyPlan::new(files_to_copy, destination_dir, &PlanOptions::from(&args));
+    let total_file_sizes = copy_plan.total_bytes();
+    let directories = copy_plan.directories();
+    let extraneous_files = copy_plan.extraneous_files().to_vec();
+    let trash_dir = copy_plan.trash_dir().map(Path::to_path_buf);
 
-    let total_file_sizes: u64 =
-      files_to_copy
-        .iter()
-        .map(|sf| sf.size())
-        .sum();
+    if args.dry_run {
+      CoopConsole::show_plan(&copy_plan, copy_options.move_sources(), &console_output);
+      return RunOutcome::Succeeded
+    }
 
     if !skip_verification {
       let selection =
         CoopConsole::show_copy_state(
-          &files_to_copy,
+          &copy_plan,
           concurrency,
-          &buffer_size,
+          copy_options.buffer_size(),
           destination_dir.to_str().unwrap_or("<Unknown>"),
-          total_file_sizes
+          copy_options.move_sources(),
+          &console_output,
         );
 
       match selection {
         UserResult::Continue => (),
-        _ => return
+        UserResult::Cancel => return RunOutcome::Cancelled,
+        UserResult::Error(e) => {
+          eprintln!("{e}");
+          return RunOutcome::PlanningFailed
+        }
       };
     }
 
+    let events_monitor =
+      match args.events.as_ref().map(EventStreamMonitor::new).transpose() {
+        Ok(monitor) => monitor,
+        Err(e) => {
+          eprintln!("{e}");
+          return RunOutcome::PlanningFailed
+        }
+      };
+
+    // Open the report up front, so a bad path fails before anything is copied
+    let report_output =
+      match args.report.as_ref().map(OutputTarget::open).transpose() {
+        Ok(output) => output,
+        Err(e) => {
+          eprintln!("Could not open the --report file due to: {e}");
+          return RunOutcome::PlanningFailed
+        }
+      };
+
+    let report =
+      report_output
+        .is_some()
+        .then(|| {
+          let files =
+            copy_plan
+              .entries()
+              .iter()
+              .filter(|e| !matches!(e.action(), CopyAction::CreateDirectory))
+              .map(|e| (e.source_file().relative_path(), e.source_file().size()));
+
+          RunReport::new(files, copy_options.checksum_verification(), Local::now())
+        });
+
     let multi = MultiProgress::new();
 
+    // Hard links can only be created once the files they link to have been copied.
+    // Directories are all created up front, so they aren't copy tasks.
+    let (hard_link_entries, copy_entries): (Vec<_>, Vec<_>) =
+      copy_plan
+        .into_entries()
+        .into_iter()
+        .filter(|e| !matches!(e.action(), CopyAction::CreateDirectory))
+        .partition(|e| matches!(e.action(), CopyAction::HardLink(_)));
+
+    // When moving, followed symlinks are copied before anything else is moved, in case they link to a file that is
+    let (followed_symlink_entries, copy_entries): (Vec<_>, Vec<_>) =
+      copy_entries
+        .into_iter()
+        .partition(|e| copy_options.move_sources() && e.source_file().is_followed_symlink());
+
+    let followed_symlink_tasks: Vec<_> =
+      followed_symlink_entries
+        .into_iter()
+        .map(|e| FileCopy::new(e, &multi) )
+        .collect();
+
     let copy_tasks: Vec<_> =
-      files_to_copy
+      copy_entries
+        .into_iter()
+        .map(|e| FileCopy::new(e, &multi) )
+        .collect();
+
+    let hard_link_tasks: Vec<_> =
+      hard_link_entries
         .into_iter()
-        .map(|f| FileCopy::new(f, destination_dir, &multi) )
+        .map(|e| FileCopy::new(e, &multi) )
         .collect();
 
     // For low cardinality events
@@ -76,52 +159,268 @@ impl CoopWorkflow {
     // Depending on the buffer size, the number of progress updates/s can be huge
     let (inprogress_sender, inprogress_receiver) = mpsc::channel::<InProgress>(100000);
 
+    // Progress is aggregated before it's sent, so this is low cardinality too
+    let (event_stream_sender, event_stream_receiver) = mpsc::channel::<FileEvent>(1000);
+    let (report_sender, report_receiver) = mpsc::channel::<FileEvent>(1000);
+
+    let event_senders: Vec<_> =
+      [
+        events_monitor.is_some().then_some(event_stream_sender),
+        report.is_some().then_some(report_sender)
+      ]
+      .into_iter()
+      .flatten()
+      .collect();
+    let event_sender = (!event_senders.is_empty()).then(|| EventSender::new(event_senders));
+
     let lifecycle_event_monitor_fut = LifecycleEventMonitor::monitor(lifecycle_event_receiver);
 
     let overall_monitor =
-      OverallProgressMonitor::new(&multi, NumFiles::new(copy_tasks.len() as u64), TotalFileSize::new(total_file_sizes))
-        .unwrap_or_else(|e| panic!("{e}"));
+      match OverallProgressMonitor::new(
+        &multi,
+        NumFiles::new((followed_symlink_tasks.len() + copy_tasks.len() + hard_link_tasks.len()) as u64),
+        TotalFileSize::new(total_file_sizes),
+        copy_options.bandwidth_limiter().clone()
+      ) {
+        Ok(monitor) => monitor,
+        Err(e) => {
+          eprintln!("{e}");
+          return RunOutcome::PlanningFailed
+        }
+      };
 
     let overall_monitor_fut = overall_monitor.monitor(overall_progress_receiver, Instant::now());
 
     let progress_monitor_fut = FileInProgressMonitor::monitor(inprogress_receiver);
 
-    let mut join_set = JoinSet::new();
+    let mut monitors = JoinSet::new();
     // Start the monitors first, so we don't miss any messages
-    join_set.spawn(lifecycle_event_monitor_fut);
-    join_set.spawn(overall_monitor_fut);
-    join_set.spawn(progress_monitor_fut);
+    monitors.spawn(lifecycle_event_monitor_fut);
+    monitors.spawn(progress_monitor_fut);
+    if let Some(events_monitor) = events_monitor {
+      monitors.spawn(events_monitor.monitor(event_stream_receiver));
+    }
+    let overall_monitor_handle = tokio::spawn(overall_monitor_fut);
+    let report_monitor_handle = report.map(|report| tokio::spawn(ReportMonitor::monitor(report_receiver, report)));
+
+    let control_file_watcher =
+      args
+        .bwlimit_file
+        .clone()
+        .map(|control_file| {
+          let bandwidth_limiter = copy_options.bandwidth_limiter().clone();
+          tokio::spawn(async move { bandwidth_limiter.watch_control_file(control_file).await })
+        });
+
+    let created_directories = {
+      let mux =
+        MonitorMux::new(
+          InProgressSender::new(inprogress_sender.clone()),
+          LifecycleEventSender::new(lifecycle_event_sender.clone()),
+          OverallProgressSender::new(overall_progress_sender.clone())
+        )
+        .with_event_sender(event_sender.clone());
+
+      Self::create_directories(&directories, destination_dir, &mux).await
+    };
+
+    for tasks in [followed_symlink_tasks, copy_tasks, hard_link_tasks] {
+      Self::run_copy_tasks(tasks, &copy_options, concurrency, &inprogress_sender, &lifecycle_event_sender, &overall_progress_sender, event_sender.as_ref()).await
+    }
+
+    if let Some(watcher) = control_file_watcher {
+      watcher.abort()
+    }
+
+    // Only remove files once everything has been copied, so an interrupted run leaves the most behind
+    if !extraneous_files.is_empty() {
+      let mux =
+        MonitorMux::new(
+          InProgressSender::new(inprogress_sender.clone()),
+          LifecycleEventSender::new(lifecycle_event_sender.clone()),
+          OverallProgressSender::new(overall_progress_sender.clone())
+        )
+        .with_event_sender(event_sender.clone());
+
+      Self::remove_extraneous_files(&extraneous_files, &directories, trash_dir.as_deref(), destination_dir, &mux).await
+    }
+
+    // Drop senders so the execution can complete
+    drop(inprogress_sender);
+    drop(lifecycle_event_sender);
+    drop(overall_progress_sender);
+    drop(event_sender);
+
+    // Wait for the monitors to process any remaining messages
+    while monitors.join_next().await.is_some() {}
+    let summary = overall_monitor_handle.await.unwrap_or_else(|e| panic!("{e}"));
+
+    if let (Some(handle), Some(output), Some(target)) = (report_monitor_handle, report_output, args.report.as_ref()) {
+      let mut report = handle.await.unwrap_or_else(|e| panic!("{e}"));
+      report.finish(Local::now());
+
+      let format = args.report_format.unwrap_or_else(|| ReportFormat::for_target(target));
+      if let Err(e) = report.write(output, format) {
+        eprintln!("Could not write the report: {e}")
+      }
+    }
+
+    // Directory timestamps change whenever a file is written into them, so these are applied last.
+    // Extended attributes and ACLs go first, as they can't be set on a read-only directory, the same order as for files.
+    Self::copy_directory_extended_attributes(&directories, &copy_options);
+    Self::copy_directory_permissions(&created_directories);
+    Self::preserve_directory_attributes(&directories, &copy_options);
+
+    if copy_options.move_sources() {
+      Self::remove_empty_source_directories(&directories)
+    }
+
+    CoopConsole::show_summary(&summary, &console_output);
+    RunOutcome::from(&summary)
+  }
+
+  /// Runs the copy tasks, at most `concurrency` at a time, and waits for all of them to complete.
+  async fn run_copy_tasks(
+    copy_tasks: Vec<FileCopy>,
+    copy_options: &CopyOptions,
+    concurrency: u8,
+    inprogress_sender: &mpsc::Sender<InProgress>,
+    lifecycle_event_sender: &mpsc::Sender<FileStatus>,
+    overall_progress_sender: &mpsc::Sender<FileStatus>,
+    event_sender: Option<&EventSender>
+  ) {
+    let mut join_set = JoinSet::new();
 
     let mut running = 0_u8;
     for task in copy_tasks {
-      join_set.spawn(
-        task.copy(
-          buffer_size.clone(),
-          MonitorMux::new(
-            InProgressSender::new(inprogress_sender.clone()),
-            LifecycleEventSender::new(lifecycle_event_sender.clone()),
-            OverallProgressSender::new(overall_progress_sender.clone())
-          )
+      let mux =
+        MonitorMux::new(
+          InProgressSender::new(inprogress_sender.clone()),
+          LifecycleEventSender::new(lifecycle_event_sender.clone()),
+          OverallProgressSender::new(overall_progress_sender.clone())
         )
-      );
+        .with_event_sender(event_sender.map(|s| s.for_file(&task.source_relative_path())));
+
+      join_set.spawn(task.copy(copy_options.clone(), mux));
       running = min(running + 1, concurrency);
 
       if running >= concurrency {
         // Wait for a single task to complete so we fall below the concurrency threshold
-        // This would only wait for file copy tasks, as the monitors will stay alive until the last sender is dropped
         let _ = join_set.join_next().await;
         running = max(running - 1, 0);
       }
     };
 
-    // Drop senders so the execution can complete
-    drop(inprogress_sender);
-    drop(lifecycle_event_sender);
-    drop(overall_progress_sender);
-
     // Wait for any running tasks to complete
     while join_set.join_next().await.is_some() {}
+  }
+
+  /// Creates the destination directories that don't exist yet, parents first, and returns them.
+  async fn create_directories(directories: &[(PathBuf, PathBuf)], destination_dir: &Path, mux: &MonitorMux) -> Vec<(PathBuf, PathBuf)> {
+    let mut created_directories = vec![];
+
+    for (source, destination) in directories.iter().rev() {
+      if destination.is_dir() {
+        continue
+      }
+
+      let relative_path = destination.strip_prefix(destination_dir).unwrap_or(destination).to_string_lossy();
+
+      match tokio::fs::create_dir_all(destination).await {
+        Ok(_) => {
+          mux.send_created_directory(&relative_path).await;
+          created_directories.push((source.clone(), destination.clone()))
+        },
+        Err(e) => mux.send_could_not_create_directory(&relative_path, <std::io::Error as Into<CopyError>>::into(e)).await
+      }
+    }
+
+    created_directories
+  }
+
+  /// Gives new directories the permissions of their source directories.
+  /// This waits until everything has been copied, as a read-only directory can't have files copied into it.
+  fn copy_directory_permissions(created_directories: &[(PathBuf, PathBuf)]) {
+    for (source_dir, destination_dir) in created_directories {
+      if let Err(e) = preserve_attributes(source_dir, destination_dir, &[PreserveAttribute::Mode]) {
+        eprintln!("Could not set permissions of {}: {e}", destination_dir.to_string_lossy())
+      }
+    }
+  }
+
+  /// Removes destination files that have no source file, and any destination directories that leaves empty.
+  /// Removes the extraneous files, then any of their parent directories that are left empty.
+  /// Directories that are in the source are kept, even when empty.
+  async fn remove_extraneous_files(extraneous_files: &[ExtraneousFile], directories: &[(PathBuf, PathBuf)], trash_dir: Option<&Path>, destination_dir: &Path, mux: &MonitorMux) {
+    let mut parent_dirs = BTreeSet::new();
+    let planned_dirs: BTreeSet<&Path> = directories.iter().map(|(_, destination)| destination.as_path()).collect();
+
+    for file in extraneous_files {
+      match remove_extraneous_file(file, trash_dir).await {
+        Ok(trashed_file) => {
+          mux.send_removed_extraneous(file.relative_path(), trashed_file).await;
+
+          file
+            .path()
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| *dir != destination_dir)
+            .filter(|dir| !planned_dirs.contains(dir))
+            .for_each(|dir| {
+              parent_dirs.insert(dir.to_path_buf());
+            })
+        },
+        Err(e) => mux.send_could_not_remove_extraneous(file.relative_path(), <std::io::Error as Into<CopyError>>::into(e)).await
+      }
+    }
+
+    // Subdirectories come first, so their parents can be empty by the time they are removed
+    let mut parent_dirs: Vec<PathBuf> = parent_dirs.into_iter().collect();
+    parent_dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+
+    for dir in parent_dirs {
+      // Fails for directories that still have files
+      let _ = std::fs::remove_dir(dir);
+    }
+  }
+
+  /// Removes source directories that moving their files has left empty.
+  /// Subdirectories come first, so their parents can be empty by the time they are removed.
+  fn remove_empty_source_directories(directories: &[(PathBuf, PathBuf)]) {
+    for (source_dir, _) in directories {
+      // Fails for directories that still have files, such as those that failed to copy
+      let _ = std::fs::remove_dir(source_dir);
+    }
+  }
+
+  fn copy_directory_extended_attributes(directories: &[(PathBuf, PathBuf)], copy_options: &CopyOptions) {
+    let attributes = copy_options.preserve();
+    if attributes.is_empty() {
+      return
+    }
+
+    for (source_dir, destination_dir) in directories {
+      // Directories that were never created, because all their files failed, are ignored
+      if destination_dir.is_dir() {
+        if let Err(e) = copy_extended_attributes(source_dir, destination_dir, attributes, copy_options.xattr_namespaces()) {
+          eprintln!("⚠️ Could not copy extended attributes of {}: {e}", destination_dir.to_string_lossy())
+        }
+      }
+    }
+  }
+
+  fn preserve_directory_attributes(directories: &[(PathBuf, PathBuf)], copy_options: &CopyOptions) {
+    let attributes = copy_options.preserve();
+    if attributes.is_empty() {
+      return
+    }
 
-    println!("See coop.log for the file list")
+    for (source_dir, destination_dir) in directories {
+      if destination_dir.is_dir() {
+        if let Err(e) = preserve_attributes(source_dir, destination_dir, attributes) {
+          eprintln!("Could not preserve attributes of {}: {e}", destination_dir.to_string_lossy())
+        }
+      }
+    }
   }
 }
@@ -1,3 +1,5 @@
 mod coop_workflow;
+mod run_outcome;
 
 pub use coop_workflow::CoopWorkflow;
+pub use run_outcome::RunOutcome;
@@ -0,0 +1,84 @@
+use std::process::ExitCode;
+
+use crate::model::RunSummary;
+
+/// How a run ended, which decides the exit code of coop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+  /// Every file was copied or skipped
+  Succeeded,
+  /// Some files failed, and the rest were copied or skipped
+  PartiallyFailed,
+  /// Every file failed
+  Failed,
+  /// The copy was cancelled at the confirmation prompt
+  Cancelled,
+  /// The copy could not be planned or started
+  PlanningFailed,
+}
+
+impl RunOutcome {
+
+  /// 2 is left out, as clap exits with it when the arguments are invalid
+  pub fn exit_code(&self) -> u8 {
+    match self {
+      RunOutcome::Succeeded => 0,
+      RunOutcome::PartiallyFailed => 1,
+      RunOutcome::Failed => 3,
+      RunOutcome::Cancelled => 4,
+      RunOutcome::PlanningFailed => 5,
+    }
+  }
+}
+
+impl From<&RunSummary> for RunOutcome {
+  fn from(summary: &RunSummary) -> Self {
+    if summary.failures().is_empty() {
+      RunOutcome::Succeeded
+    } else if summary.succeeded() + summary.skipped() > 0 {
+      RunOutcome::PartiallyFailed
+    } else {
+      RunOutcome::Failed
+    }
+  }
+}
+
+impl From<RunOutcome> for ExitCode {
+  fn from(outcome: RunOutcome) -> Self {
+    ExitCode::from(outcome.exit_code())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn succeeds_without_failures() {
+      let mut summary = RunSummary::default();
+      summary.add_success();
+      summary.add_skip();
+
+      assert_eq!(RunOutcome::from(&summary), RunOutcome::Succeeded);
+      assert_eq!(RunOutcome::from(&RunSummary::default()), RunOutcome::Succeeded)
+    }
+
+    #[test]
+    fn partially_fails_when_some_files_were_copied_or_skipped() {
+      let mut summary = RunSummary::default();
+      summary.add_skip();
+      summary.add_failure("file.txt", "Read failed: Input/output error");
+
+      assert_eq!(RunOutcome::from(&summary), RunOutcome::PartiallyFailed)
+    }
+
+    #[test]
+    fn fails_when_nothing_was_copied_or_skipped() {
+      let mut summary = RunSummary::default();
+      summary.add_failure("dir/", "Could not create directory: File exists");
+      summary.add_failure("file.txt", "Could not create destination dir: File exists");
+
+      assert_eq!(RunOutcome::from(&summary), RunOutcome::Failed)
+    }
+}
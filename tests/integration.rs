@@ -17,3 +17,1507 @@ fn returns_version() -> Result<(), Box<dyn std::error::Error>> {
 
   Ok(())
 }
+
+#[test]
+fn resumes_partially_copied_file() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  let content: Vec<u8> = (0..100_000_u32).map(|i| (i % 251) as u8).collect();
+  let source_file = source_dir.path().join("data.bin");
+  std::fs::write(&source_file, &content)?;
+  std::fs::write(destination_dir.path().join(".data.bin.coop-partial"), &content[..40_000])?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(&source_file)
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("-b")
+    .arg("8KB")
+    .arg("--resume")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert_eq!(std::fs::read(destination_dir.path().join("data.bin"))?, content);
+  assert!(!destination_dir.path().join(".data.bin.coop-partial").exists());
+
+  Ok(())
+}
+
+#[test]
+fn does_not_resume_a_full_size_partial_file() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  // As left by a ranged copy that only finished its last range
+  let content: Vec<u8> = (0..100_000_u32).map(|i| (i % 251) as u8).collect();
+  let mut partial = vec![0; 75_000];
+  partial.extend_from_slice(&content[75_000..]);
+
+  let source_file = source_dir.path().join("data.bin");
+  std::fs::write(&source_file, &content)?;
+  std::fs::write(destination_dir.path().join(".data.bin.coop-partial"), &partial)?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(&source_file)
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("-b")
+    .arg("8KB")
+    .arg("--resume")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert_eq!(std::fs::read(destination_dir.path().join("data.bin"))?, content);
+
+  Ok(())
+}
+
+#[test]
+fn skips_up_to_date_files_in_incremental_mode() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("same.txt"), "same content")?;
+  std::fs::write(source_dir.path().join("changed.txt"), "new content")?;
+  std::fs::write(source_dir.path().join("new.txt"), "brand new")?;
+  std::fs::write(destination_dir.path().join("same.txt"), "same content")?;
+  std::fs::write(destination_dir.path().join("changed.txt"), "old content")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--incremental")
+    .arg("--compare")
+    .arg("checksum")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("changed.txt"))?, "new content");
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("new.txt"))?, "brand new");
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("same.txt (12B) ⏭️ skipped: up to date (same checksum)"), "{log}");
+  assert!(log.contains("changed.txt (11B) ✅"), "{log}");
+  assert!(log.contains("new.txt (9B) ✅"), "{log}");
+
+  Ok(())
+}
+
+#[test]
+fn verifies_checksums_of_copied_and_resumed_files() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  let content: Vec<u8> = (0..50_000_u32).map(|i| (i % 13) as u8).collect();
+  std::fs::write(source_dir.path().join("fresh.bin"), &content)?;
+  std::fs::write(source_dir.path().join("partial.bin"), &content)?;
+  std::fs::write(destination_dir.path().join(".partial.bin.coop-partial"), &content[..20_000])?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("-b")
+    .arg("4KB")
+    .arg("--resume")
+    .arg("--verify")
+    .arg("checksum")
+    .arg("--checksum")
+    .arg("sha256")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert_eq!(std::fs::read(destination_dir.path().join("fresh.bin"))?, content);
+  assert_eq!(std::fs::read(destination_dir.path().join("partial.bin"))?, content);
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("fresh.bin (48.83KiB) ✅"), "{log}");
+  assert!(log.contains("partial.bin (48.83KiB) ✅"), "{log}");
+
+  Ok(())
+}
+
+#[test]
+fn removes_partial_file_when_copy_fails() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("blocked.txt"), "content")?;
+  // A non-empty directory can't be replaced by the copied file
+  std::fs::create_dir_all(destination_dir.path().join("blocked.txt").join("child"))?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--skip-verify")
+    .assert()
+    // Every file failed
+    .code(3);
+
+  assert!(destination_dir.path().join("blocked.txt").join("child").is_dir());
+  assert!(!destination_dir.path().join(".blocked.txt.coop-partial").exists());
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("blocked.txt ("), "{log}");
+  assert!(log.contains("❌"), "{log}");
+
+  Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn exits_with_partial_failure_and_lists_failed_files() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("copied.txt"), "content")?;
+  std::fs::write(source_dir.path().join("blocked.txt"), "content")?;
+  std::fs::create_dir_all(destination_dir.path().join("blocked.txt").join("child"))?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  let output =
+    cmd
+      .current_dir(working_dir.path())
+      .arg("-s")
+      .arg(source_dir.path())
+      .arg("-d")
+      .arg(destination_dir.path())
+      .arg("--skip-verify")
+      .assert()
+      .code(1)
+      .get_output()
+      .stdout
+      .clone();
+
+  let stdout = String::from_utf8(output)?;
+  assert!(stdout.contains("Summary: 1 copied, 0 skipped, 1 failed"), "{stdout}");
+  assert!(stdout.contains("blocked.txt  Could not move partial file into place"), "{stdout}");
+  assert!(destination_dir.path().join("copied.txt").is_file());
+
+  Ok(())
+}
+
+#[test]
+fn lists_failed_files_by_their_path_in_the_source() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  for dir in ["a", "b"] {
+    std::fs::create_dir(source_dir.path().join(dir))?;
+    std::fs::write(source_dir.path().join(dir).join("x.txt"), "content")?;
+    std::fs::create_dir_all(destination_dir.path().join(dir).join("x.txt").join("child"))?;
+  }
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  let output =
+    cmd
+      .current_dir(working_dir.path())
+      .arg("-s")
+      .arg(source_dir.path())
+      .arg("-d")
+      .arg(destination_dir.path())
+      .arg("--skip-verify")
+      .assert()
+      .code(3)
+      .get_output()
+      .stdout
+      .clone();
+
+  let stdout = String::from_utf8(output)?;
+  for file in [std::path::Path::new("a").join("x.txt"), std::path::Path::new("b").join("x.txt")] {
+    assert!(stdout.contains(&format!("{}  Could not move partial file into place", file.display())), "{stdout}");
+  }
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains(&std::path::Path::new("a").join("x.txt").display().to_string()), "{log}");
+
+  Ok(())
+}
+
+#[test]
+fn names_the_destination_file_when_its_partial_file_cannot_be_created() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("f.txt"), "content")?;
+  std::fs::create_dir(destination_dir.path().join(".f.txt.coop-partial"))?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  let output =
+    cmd
+      .current_dir(working_dir.path())
+      .arg("-s")
+      .arg(source_dir.path())
+      .arg("-d")
+      .arg(destination_dir.path())
+      .arg("--skip-verify")
+      .assert()
+      .code(3)
+      .get_output()
+      .stdout
+      .clone();
+
+  let stdout = String::from_utf8(output)?;
+  assert!(stdout.contains("f.txt  Could not create destination file"), "{stdout}");
+  assert!(!stdout.contains("coop-partial"), "{stdout}");
+
+  Ok(())
+}
+
+#[test]
+fn exits_with_planning_failure_when_source_is_missing() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(working_dir.path().join("missing"))
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--skip-verify")
+    .assert()
+    .code(5);
+
+  assert!(!working_dir.path().join("coop.log").exists());
+
+  Ok(())
+}
+
+#[test]
+fn preserves_mode_and_timestamps() -> Result<(), Box<dyn std::error::Error>> {
+  use std::os::unix::fs::PermissionsExt;
+  use filetime::FileTime;
+
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  let nested_dir = source_dir.path().join("nested");
+  let source_file = nested_dir.join("script.sh");
+  std::fs::create_dir(&nested_dir)?;
+  std::fs::write(&source_file, "#!/bin/sh")?;
+  std::fs::set_permissions(&source_file, std::fs::Permissions::from_mode(0o750))?;
+
+  let file_mtime = FileTime::from_unix_time(1_600_000_000, 123_456_789);
+  let dir_mtime = FileTime::from_unix_time(1_500_000_000, 987_654_321);
+  filetime::set_file_times(&source_file, file_mtime, file_mtime)?;
+  filetime::set_file_times(&nested_dir, dir_mtime, dir_mtime)?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--preserve")
+    .arg("mode,timestamps")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  let copied_file = std::fs::metadata(destination_dir.path().join("nested").join("script.sh"))?;
+  assert_eq!(copied_file.permissions().mode() & 0o777, 0o750);
+  assert_eq!(FileTime::from_last_modification_time(&copied_file), file_mtime);
+
+  let copied_dir = std::fs::metadata(destination_dir.path().join("nested"))?;
+  assert_eq!(FileTime::from_last_modification_time(&copied_dir), dir_mtime);
+
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn copies_extended_attributes_in_namespace() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  let source_file = source_dir.path().join("tagged.txt");
+  std::fs::write(&source_file, "content")?;
+
+  // Not every filesystem supports user xattrs
+  if xattr::set(&source_file, "user.coop.tag", b"archive").is_err() {
+    return Ok(())
+  }
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--preserve")
+    .arg("xattrs")
+    .arg("--xattr-namespace")
+    .arg("user")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  let tag = xattr::get(destination_dir.path().join("tagged.txt"), "user.coop.tag")?;
+  assert_eq!(tag, Some(b"archive".to_vec()));
+
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn copies_extended_attributes_of_read_only_directories() -> Result<(), Box<dyn std::error::Error>> {
+  use std::os::unix::fs::PermissionsExt;
+
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  let archive_dir = source_dir.path().join("archive");
+  std::fs::create_dir(&archive_dir)?;
+  std::fs::write(archive_dir.join("file.txt"), "content")?;
+
+  // Not every filesystem supports user xattrs
+  if xattr::set(&archive_dir, "user.coop.tag", b"archive").is_err() {
+    return Ok(())
+  }
+  std::fs::set_permissions(&archive_dir, std::fs::Permissions::from_mode(0o555))?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--preserve")
+    .arg("mode,timestamps,xattrs")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  let copied_dir = destination_dir.path().join("archive");
+  assert_eq!(xattr::get(&copied_dir, "user.coop.tag")?, Some(b"archive".to_vec()));
+  assert_eq!(std::fs::metadata(&copied_dir)?.permissions().mode() & 0o777, 0o555);
+  assert_eq!(std::fs::read_to_string(copied_dir.join("file.txt"))?, "content");
+
+  // So the temporary directories can be cleaned up
+  std::fs::set_permissions(&archive_dir, std::fs::Permissions::from_mode(0o755))?;
+  std::fs::set_permissions(&copied_dir, std::fs::Permissions::from_mode(0o755))?;
+
+  Ok(())
+}
+
+#[cfg(unix)]
+fn symlinked_source() -> Result<(tempfile::TempDir, tempfile::TempDir), Box<dyn std::error::Error>> {
+  let source_dir = tempfile::tempdir()?;
+  let outside_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("a.txt"), "inside")?;
+  std::fs::write(outside_dir.path().join("b.txt"), "outside")?;
+  std::os::unix::fs::symlink("a.txt", source_dir.path().join("link.txt"))?;
+  std::os::unix::fs::symlink(outside_dir.path().join("b.txt"), source_dir.path().join("external.txt"))?;
+  std::os::unix::fs::symlink(".", source_dir.path().join("loop"))?;
+
+  Ok((source_dir, outside_dir))
+}
+
+#[cfg(unix)]
+fn copy_with_symlinks(source_dir: &std::path::Path, destination_dir: &std::path::Path, working_dir: &std::path::Path, mode: &str) {
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir)
+    .arg("-s")
+    .arg(source_dir)
+    .arg("-d")
+    .arg(destination_dir)
+    .arg("--symlinks")
+    .arg(mode)
+    .arg("--skip-verify")
+    .assert()
+    .success();
+}
+
+#[cfg(unix)]
+#[test]
+fn recreates_symlinks_and_skips_external_links() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+  let (source_dir, _outside_dir) = symlinked_source()?;
+
+  copy_with_symlinks(source_dir.path(), destination_dir.path(), working_dir.path(), "recreate");
+
+  assert_eq!(std::fs::read_link(destination_dir.path().join("link.txt"))?, std::path::PathBuf::from("a.txt"));
+  assert!(!destination_dir.path().join("external.txt").exists());
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("external.txt (0B) ⏭️ skipped: symlink to"), "{log}");
+  assert!(log.contains("points outside the source"), "{log}");
+
+  Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn follows_symlinks_and_skips_loops() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+  let (source_dir, _outside_dir) = symlinked_source()?;
+  std::os::unix::fs::symlink("missing.txt", source_dir.path().join("broken.txt"))?;
+
+  copy_with_symlinks(source_dir.path(), destination_dir.path(), working_dir.path(), "follow");
+
+  let link_metadata = std::fs::symlink_metadata(destination_dir.path().join("link.txt"))?;
+  assert!(link_metadata.is_file());
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("link.txt"))?, "inside");
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("loop (0B) ⏭️ skipped: symlink to . loops back to a parent directory"), "{log}");
+  assert!(log.contains("broken.txt (0B) ⏭️ skipped: symlink to missing.txt is broken"), "{log}");
+
+  Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn moves_followed_symlinks_as_the_files_they_link_to() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+  let (source_dir, outside_dir) = symlinked_source()?;
+  std::fs::remove_file(source_dir.path().join("loop"))?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--symlinks")
+    .arg("follow")
+    .arg("--external-symlinks")
+    .arg("allow")
+    .arg("--move")
+    .arg("--verify")
+    .arg("checksum")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  // Links to a file inside the tree, which is moved too, and outside it
+  for (file, content) in [("a.txt", "inside"), ("link.txt", "inside"), ("external.txt", "outside")] {
+    let copied = destination_dir.path().join(file);
+    assert!(std::fs::symlink_metadata(&copied)?.is_file(), "{file}");
+    assert_eq!(std::fs::read_to_string(&copied)?, content);
+    assert!(std::fs::symlink_metadata(source_dir.path().join(file)).is_err(), "{file}");
+  }
+
+  // Only the links are moved, not the files they link to
+  assert_eq!(std::fs::read_to_string(outside_dir.path().join("b.txt"))?, "outside");
+
+  Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn skips_symlinks_by_default() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+  let (source_dir, _outside_dir) = symlinked_source()?;
+
+  copy_with_symlinks(source_dir.path(), destination_dir.path(), working_dir.path(), "skip");
+
+  assert!(std::fs::symlink_metadata(destination_dir.path().join("link.txt")).is_err());
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("a.txt"))?, "inside");
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("link.txt (0B) ⏭️ skipped: symlink to a.txt"), "{log}");
+
+  Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn preserves_hard_links_within_the_source_tree() -> Result<(), Box<dyn std::error::Error>> {
+  use std::os::unix::fs::MetadataExt;
+
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::create_dir(source_dir.path().join("nested"))?;
+  std::fs::write(source_dir.path().join("a.bin"), vec![1_u8; 5000])?;
+  std::fs::hard_link(source_dir.path().join("a.bin"), source_dir.path().join("nested").join("b.bin"))?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  let first = std::fs::metadata(destination_dir.path().join("a.bin"))?;
+  let second = std::fs::metadata(destination_dir.path().join("nested").join("b.bin"))?;
+  assert_eq!(first.ino(), second.ino());
+  assert_eq!(first.nlink(), 2);
+  assert_eq!(std::fs::read(destination_dir.path().join("nested").join("b.bin"))?, vec![1_u8; 5000]);
+
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn copies_holes_in_sparse_files() -> Result<(), Box<dyn std::error::Error>> {
+  use std::io::{Seek, SeekFrom, Write};
+  use std::os::unix::fs::MetadataExt;
+
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  // 32MiB file with data at 8MiB and a trailing hole
+  let hole = 8 * 1024 * 1024;
+  let mut sparse_file = std::fs::File::create(source_dir.path().join("disk.img"))?;
+  sparse_file.seek(SeekFrom::Start(hole))?;
+  sparse_file.write_all(b"data in the middle")?;
+  sparse_file.set_len(hole * 4)?;
+  drop(sparse_file);
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--verify")
+    .arg("checksum")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  let source = std::fs::read(source_dir.path().join("disk.img"))?;
+  let copied = std::fs::read(destination_dir.path().join("disk.img"))?;
+  assert_eq!(copied.len(), source.len());
+  assert!(copied == source);
+
+  // Only check the holes were kept where the filesystem supports them
+  let source_meta = std::fs::metadata(source_dir.path().join("disk.img"))?;
+  if source_meta.blocks() * 512 < source_meta.len() {
+    let copied_meta = std::fs::metadata(destination_dir.path().join("disk.img"))?;
+    assert!(copied_meta.blocks() * 512 < copied_meta.len() / 4, "{} blocks", copied_meta.blocks());
+  }
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("disk.img (32.00MiB)"), "{log}");
+
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn copies_with_kernel_acceleration() -> Result<(), Box<dyn std::error::Error>> {
+  use std::io::{Seek, SeekFrom, Write};
+
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  // Larger than the buffer, so it's copied in several chunks
+  let data: Vec<u8> = (0..3 * 1024 * 1024 + 17).map(|i| (i % 251) as u8).collect();
+  std::fs::write(source_dir.path().join("data.bin"), &data)?;
+
+  let hole = 4 * 1024 * 1024;
+  let mut sparse_file = std::fs::File::create(source_dir.path().join("disk.img"))?;
+  sparse_file.seek(SeekFrom::Start(hole))?;
+  sparse_file.write_all(b"data in the middle")?;
+  sparse_file.set_len(hole * 2)?;
+  drop(sparse_file);
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--accelerate")
+    .arg("--verify")
+    .arg("checksum")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert!(std::fs::read(destination_dir.path().join("data.bin"))? == data);
+  assert!(std::fs::read(destination_dir.path().join("disk.img"))? == std::fs::read(source_dir.path().join("disk.img"))?);
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("data.bin (3.00MiB)"), "{log}");
+  assert!(log.contains("disk.img (8.00MiB)"), "{log}");
+
+  Ok(())
+}
+
+#[test]
+fn limits_bandwidth_across_concurrent_copies() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("one.bin"), vec![1_u8; 1024 * 1024])?;
+  std::fs::write(source_dir.path().join("two.bin"), vec![2_u8; 1024 * 1024])?;
+
+  let start = std::time::Instant::now();
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("-c")
+    .arg("2")
+    .arg("--bwlimit")
+    .arg("1MB/s")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  // 2MiB at 1MiB/s, whatever the concurrency
+  assert!(start.elapsed() >= std::time::Duration::from_millis(1900), "{:?}", start.elapsed());
+  assert_eq!(std::fs::read(destination_dir.path().join("two.bin"))?, vec![2_u8; 1024 * 1024]);
+
+  Ok(())
+}
+
+fn move_files(source_dir: &std::path::Path, destination_dir: &std::path::Path, working_dir: &std::path::Path) {
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir)
+    .arg("-s")
+    .arg(source_dir)
+    .arg("-d")
+    .arg(destination_dir)
+    .arg("--move")
+    .arg("--verify")
+    .arg("checksum")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+}
+
+#[test]
+fn moves_files_and_removes_emptied_source_directories() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::create_dir_all(source_dir.path().join("DCIM").join("100"))?;
+  std::fs::write(source_dir.path().join("DCIM").join("100").join("IMG_0001.JPG"), "photo")?;
+  std::fs::write(source_dir.path().join("notes.txt"), "notes")?;
+
+  move_files(source_dir.path(), destination_dir.path(), working_dir.path());
+
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("DCIM").join("100").join("IMG_0001.JPG"))?, "photo");
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("notes.txt"))?, "notes");
+  assert!(!source_dir.path().join("DCIM").exists());
+  assert!(!source_dir.path().join("notes.txt").exists());
+  assert!(source_dir.path().exists());
+
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn moves_files_across_filesystems_by_copying() -> Result<(), Box<dyn std::error::Error>> {
+  // /dev/shm is a tmpfs, so renames into it from the default temp directory fail
+  let Ok(working_dir) = tempfile::tempdir_in("/dev/shm") else {
+    return Ok(())
+  };
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir_in("/dev/shm")?;
+
+  std::fs::create_dir(source_dir.path().join("nested"))?;
+  std::fs::write(source_dir.path().join("nested").join("data.bin"), vec![7_u8; 5000])?;
+
+  move_files(source_dir.path(), destination_dir.path(), working_dir.path());
+
+  assert_eq!(std::fs::read(destination_dir.path().join("nested").join("data.bin"))?, vec![7_u8; 5000]);
+  assert!(!source_dir.path().join("nested").exists());
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("data.bin (4.88KiB) ✅"), "{log}");
+
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn backs_up_once_when_moving_across_filesystems() -> Result<(), Box<dyn std::error::Error>> {
+  let Ok(working_dir) = tempfile::tempdir_in("/dev/shm") else {
+    return Ok(())
+  };
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir_in("/dev/shm")?;
+
+  std::fs::write(source_dir.path().join("f.txt"), "new")?;
+  std::fs::write(destination_dir.path().join("f.txt"), "old")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--move")
+    .arg("--backup")
+    .arg("suffix")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("f.txt"))?, "new");
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("f.txt~"))?, "old");
+  assert!(!destination_dir.path().join("f.txt~.~1~").exists());
+
+  Ok(())
+}
+
+fn mirrored_destination() -> Result<(tempfile::TempDir, tempfile::TempDir), Box<dyn std::error::Error>> {
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("kept.txt"), "new")?;
+  std::fs::write(destination_dir.path().join("kept.txt"), "old")?;
+  std::fs::create_dir(destination_dir.path().join("old"))?;
+  std::fs::write(destination_dir.path().join("old").join("stale.txt"), "stale")?;
+  std::fs::write(destination_dir.path().join(".DS_Store"), "ignored")?;
+
+  Ok((source_dir, destination_dir))
+}
+
+#[test]
+fn deletes_extraneous_destination_files() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let (source_dir, destination_dir) = mirrored_destination()?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--delete")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("kept.txt"))?, "new");
+  assert!(!destination_dir.path().join("old").exists());
+  assert!(destination_dir.path().join(".DS_Store").exists());
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains(&format!("{} 🗑️ deleted", std::path::Path::new("old").join("stale.txt").display())), "{log}");
+
+  Ok(())
+}
+
+#[test]
+fn keeps_empty_source_directories_when_deleting_extraneous_files() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::create_dir(source_dir.path().join("logs"))?;
+  std::fs::create_dir(destination_dir.path().join("logs"))?;
+  std::fs::write(destination_dir.path().join("logs").join("stale.log"), "stale")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--delete")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert!(!destination_dir.path().join("logs").join("stale.log").exists());
+  assert!(destination_dir.path().join("logs").is_dir());
+
+  Ok(())
+}
+
+#[test]
+fn moves_extraneous_destination_files_to_trash() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let trash_dir = tempfile::tempdir()?;
+  let (source_dir, destination_dir) = mirrored_destination()?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--delete")
+    .arg("--trash-dir")
+    .arg(trash_dir.path())
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert!(!destination_dir.path().join("old").exists());
+  assert_eq!(std::fs::read_to_string(trash_dir.path().join("old").join("stale.txt"))?, "stale");
+
+  Ok(())
+}
+
+#[test]
+fn dry_run_prints_the_plan_without_writing() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::create_dir(source_dir.path().join("nested"))?;
+  std::fs::write(source_dir.path().join("nested").join("new.txt"), "new")?;
+  std::fs::write(source_dir.path().join("existing.txt"), "existing")?;
+  std::fs::write(destination_dir.path().join("existing.txt"), "old")?;
+  std::fs::write(destination_dir.path().join("stale.txt"), "stale")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  let output =
+    cmd
+      .current_dir(working_dir.path())
+      .arg("-s")
+      .arg(source_dir.path())
+      .arg("-d")
+      .arg(destination_dir.path())
+      .arg("--delete")
+      .arg("--dry-run")
+      .assert()
+      .success()
+      .get_output()
+      .stdout
+      .clone();
+
+  let plan = String::from_utf8(output)?;
+  let new_file = std::path::Path::new("nested").join("new.txt");
+  assert!(plan.contains(&format!("mkdir      {}", destination_dir.path().join("nested").display())), "{plan}");
+  assert!(plan.contains(&format!("copy       {} (3B): new file", new_file.display())), "{plan}");
+  assert!(plan.contains("copy       existing.txt (8B): replaces existing file"), "{plan}");
+  assert!(plan.contains("delete     stale.txt (5B)"), "{plan}");
+
+  assert!(!destination_dir.path().join("nested").exists());
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("existing.txt"))?, "old");
+  assert!(destination_dir.path().join("stale.txt").exists());
+  assert!(!working_dir.path().join("coop.log").exists());
+
+  Ok(())
+}
+
+fn copy_with_overwrite_policy(source_dir: &std::path::Path, destination_dir: &std::path::Path, working_dir: &std::path::Path, policy: &str) {
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir)
+    .arg("-s")
+    .arg(source_dir)
+    .arg("-d")
+    .arg(destination_dir)
+    .arg("--overwrite")
+    .arg(policy)
+    .arg("--skip-verify")
+    .assert()
+    .success();
+}
+
+#[test]
+fn never_overwrites_existing_files() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("existing.txt"), "new")?;
+  std::fs::write(source_dir.path().join("missing.txt"), "new")?;
+  std::fs::write(destination_dir.path().join("existing.txt"), "old")?;
+
+  copy_with_overwrite_policy(source_dir.path(), destination_dir.path(), working_dir.path(), "never");
+
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("existing.txt"))?, "old");
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("missing.txt"))?, "new");
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("existing.txt (3B) ⏭️ skipped: destination already exists"), "{log}");
+
+  Ok(())
+}
+
+#[test]
+fn renames_copies_of_existing_files() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("report.txt"), "new")?;
+  std::fs::write(destination_dir.path().join("report.txt"), "old")?;
+  std::fs::write(destination_dir.path().join("report (1).txt"), "older")?;
+
+  copy_with_overwrite_policy(source_dir.path(), destination_dir.path(), working_dir.path(), "rename");
+
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("report.txt"))?, "old");
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("report (1).txt"))?, "older");
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("report (2).txt"))?, "new");
+
+  Ok(())
+}
+
+#[test]
+fn backs_up_replaced_files_with_a_suffix() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("report.txt"), "new")?;
+  std::fs::write(source_dir.path().join("added.txt"), "new")?;
+  std::fs::write(destination_dir.path().join("report.txt"), "old")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--backup")
+    .arg("suffix")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("report.txt"))?, "new");
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("report.txt~"))?, "old");
+  assert!(!destination_dir.path().join("added.txt~").exists());
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains(&format!("report.txt 💾 backed up to {}", destination_dir.path().join("report.txt~").display())), "{log}");
+
+  Ok(())
+}
+
+#[test]
+fn keeps_suffix_backups_when_deleting_extraneous_files() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(destination_dir.path().join("a.txt"), "first")?;
+  std::fs::write(destination_dir.path().join("stale.txt"), "stale")?;
+
+  for contents in ["second", "third"] {
+    std::fs::write(source_dir.path().join("a.txt"), contents)?;
+
+    let mut cmd = Command::cargo_bin("coop").unwrap();
+
+    cmd
+      .current_dir(working_dir.path())
+      .arg("-s")
+      .arg(source_dir.path())
+      .arg("-d")
+      .arg(destination_dir.path())
+      .arg("--delete")
+      .arg("--backup")
+      .arg("suffix")
+      .arg("--skip-verify")
+      .assert()
+      .success();
+  }
+
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("a.txt"))?, "third");
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("a.txt~"))?, "first");
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("a.txt~.~1~"))?, "second");
+  assert!(!destination_dir.path().join("stale.txt").exists());
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(!log.contains("a.txt~ 🗑️ deleted"), "{log}");
+
+  Ok(())
+}
+
+#[test]
+fn backs_up_replaced_files_into_a_directory() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+  let backup_dir = tempfile::tempdir()?;
+
+  std::fs::create_dir(source_dir.path().join("docs"))?;
+  std::fs::write(source_dir.path().join("docs").join("report.txt"), "new")?;
+  std::fs::create_dir(destination_dir.path().join("docs"))?;
+  std::fs::write(destination_dir.path().join("docs").join("report.txt"), "old")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--backup-dir")
+    .arg(backup_dir.path())
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("docs").join("report.txt"))?, "new");
+  assert_eq!(std::fs::read_to_string(backup_dir.path().join("docs").join("report.txt"))?, "old");
+
+  Ok(())
+}
+
+#[test]
+fn creates_empty_directories_with_their_permissions() -> Result<(), Box<dyn std::error::Error>> {
+  use std::os::unix::fs::PermissionsExt;
+
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::create_dir_all(source_dir.path().join("app").join("logs"))?;
+  std::fs::create_dir(source_dir.path().join("app").join("tmp"))?;
+  std::fs::set_permissions(source_dir.path().join("app").join("tmp"), std::fs::Permissions::from_mode(0o750))?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert!(destination_dir.path().join("app").join("logs").is_dir());
+  let tmp_mode = std::fs::metadata(destination_dir.path().join("app").join("tmp"))?.permissions().mode();
+  assert_eq!(tmp_mode & 0o777, 0o750);
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains(&format!("{}/ 📁 created", std::path::Path::new("app").join("logs").display())), "{log}");
+
+  Ok(())
+}
+
+#[test]
+fn copies_large_files_as_parallel_ranges() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  // Larger than the buffer, so each range is copied in several chunks
+  let data: Vec<u8> = (0..5 * 1024 * 1024 + 13).map(|i| (i % 251) as u8).collect();
+  std::fs::write(source_dir.path().join("huge.bin"), &data)?;
+  std::fs::write(source_dir.path().join("small.txt"), "below the threshold")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--ranges")
+    .arg("4")
+    .arg("--range-threshold")
+    .arg("1MB")
+    .arg("--verify")
+    .arg("checksum")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert!(std::fs::read(destination_dir.path().join("huge.bin"))? == data);
+  assert_eq!(std::fs::read_to_string(destination_dir.path().join("small.txt"))?, "below the threshold");
+
+  let log = std::fs::read_to_string(working_dir.path().join("coop.log"))?;
+  assert!(log.contains("huge.bin (5.00MiB) ✅"), "{log}");
+
+  Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn keeps_holes_when_copying_parallel_ranges() -> Result<(), Box<dyn std::error::Error>> {
+  use std::io::{Seek, SeekFrom, Write};
+  use std::os::unix::fs::MetadataExt;
+
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  // 32MiB file with data in the second and last ranges only
+  let range = 8 * 1024 * 1024;
+  let mut sparse_file = std::fs::File::create(source_dir.path().join("disk.img"))?;
+  sparse_file.seek(SeekFrom::Start(range + 4096))?;
+  sparse_file.write_all(b"data in the second range")?;
+  sparse_file.seek(SeekFrom::Start(range * 4 - 4096))?;
+  sparse_file.write_all(b"data at the end")?;
+  drop(sparse_file);
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--ranges")
+    .arg("4")
+    .arg("--range-threshold")
+    .arg("1MB")
+    .arg("--verify")
+    .arg("checksum")
+    .arg("--skip-verify")
+    .assert()
+    .success();
+
+  assert!(std::fs::read(destination_dir.path().join("disk.img"))? == std::fs::read(source_dir.path().join("disk.img"))?);
+
+  // Only check the holes were kept where the filesystem supports them
+  let source_meta = std::fs::metadata(source_dir.path().join("disk.img"))?;
+  if source_meta.blocks() * 512 < source_meta.len() {
+    let copied_meta = std::fs::metadata(destination_dir.path().join("disk.img"))?;
+    assert!(copied_meta.blocks() * 512 < copied_meta.len() / 4, "{} blocks", copied_meta.blocks());
+  }
+
+  Ok(())
+}
+
+#[test]
+fn syncs_files_and_directories() -> Result<(), Box<dyn std::error::Error>> {
+  for sync in ["none", "file", "file+dir"] {
+    let working_dir = tempfile::tempdir()?;
+    let source_dir = tempfile::tempdir()?;
+    let destination_dir = tempfile::tempdir()?;
+
+    std::fs::create_dir(source_dir.path().join("nested"))?;
+    std::fs::write(source_dir.path().join("nested").join("synced.txt"), "durable")?;
+
+    let mut cmd = Command::cargo_bin("coop").unwrap();
+
+    cmd
+      .current_dir(working_dir.path())
+      .arg("-s")
+      .arg(source_dir.path())
+      .arg("-d")
+      .arg(destination_dir.path())
+      .arg("--sync")
+      .arg(sync)
+      .arg("--skip-verify")
+      .assert()
+      .success();
+
+    assert_eq!(std::fs::read_to_string(destination_dir.path().join("nested").join("synced.txt"))?, "durable", "--sync {sync}");
+  }
+
+  Ok(())
+}
+
+#[test]
+fn writes_file_events_as_ndjson() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::create_dir(source_dir.path().join("nested"))?;
+  std::fs::write(source_dir.path().join("nested").join("file.txt"), "content")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--skip-verify")
+    .arg("--events")
+    .arg("events.ndjson")
+    .assert()
+    .success();
+
+  let events: Vec<serde_json::Value> =
+    std::fs::read_to_string(working_dir.path().join("events.ndjson"))?
+      .lines()
+      .map(serde_json::from_str)
+      .collect::<Result<_, _>>()?;
+
+  let file_events: Vec<_> = events.iter().filter(|e| e["path"] == "nested/file.txt").collect();
+  assert_eq!(file_events.first().map(|e| &e["event"]), Some(&serde_json::json!("not_started")));
+  assert_eq!(file_events.last().map(|e| (&e["event"], &e["bytes"])), Some((&serde_json::json!("success"), &serde_json::json!(7))));
+  assert!(file_events.iter().any(|e| e["event"] == "progress" && e["copied"] == 7), "{file_events:?}");
+  assert!(events.iter().any(|e| e["path"] == "nested" && e["event"] == "created_directory"), "{events:?}");
+  assert!(events.iter().all(|e| e["timestamp"].is_string()), "{events:?}");
+
+  Ok(())
+}
+
+#[test]
+fn writes_file_events_to_stdout() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("copied.txt"), "content")?;
+  std::fs::write(source_dir.path().join("blocked.txt"), "content")?;
+  std::fs::create_dir_all(destination_dir.path().join("blocked.txt").join("child"))?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  let output =
+    cmd
+      .current_dir(working_dir.path())
+      .arg("-s")
+      .arg(source_dir.path())
+      .arg("-d")
+      .arg(destination_dir.path())
+      .arg("--skip-verify")
+      .arg("--events")
+      .arg("-")
+      .assert()
+      .code(1)
+      .get_output()
+      .clone();
+
+  // Only events are written to stdout, so every line parses
+  let events: Vec<serde_json::Value> =
+    String::from_utf8(output.stdout)?
+      .lines()
+      .map(serde_json::from_str)
+      .collect::<Result<_, _>>()?;
+
+  let failed = events.iter().find(|e| e["event"] == "failed").ok_or("no failed event")?;
+  assert_eq!(failed["path"], "blocked.txt");
+  assert_eq!(failed["reason"], "could_not_rename_partial_file");
+  assert!(failed["error"].as_str().is_some_and(|e| e.starts_with("Could not move partial file into place")), "{failed}");
+  assert!(String::from_utf8(output.stderr)?.contains("Summary: 1 copied, 0 skipped, 1 failed"));
+
+  Ok(())
+}
+
+#[test]
+fn keeps_the_dry_run_plan_off_stdout_with_events() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("file.txt"), "content")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  let output =
+    cmd
+      .current_dir(working_dir.path())
+      .arg("-s")
+      .arg(source_dir.path())
+      .arg("-d")
+      .arg(destination_dir.path())
+      .arg("--dry-run")
+      .arg("--events")
+      .arg("-")
+      .assert()
+      .success()
+      .get_output()
+      .clone();
+
+  let stderr = String::from_utf8(output.stderr)?;
+  assert_eq!(String::from_utf8(output.stdout)?, "", "{stderr}");
+  assert!(stderr.contains("copy       file.txt (7B): new file"), "{stderr}");
+
+  Ok(())
+}
+
+#[test]
+fn rejects_events_and_report_both_on_stdout() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("file.txt"), "content")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--skip-verify")
+    .arg("--events")
+    .arg("-")
+    .arg("--report")
+    .arg("-")
+    .assert()
+    .code(2)
+    .stderr(predicates::str::contains("--events and --report can't both be written to stdout"));
+
+  assert!(!destination_dir.path().join("file.txt").exists());
+
+  Ok(())
+}
+
+#[test]
+fn writes_a_json_report_of_the_run() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::create_dir(source_dir.path().join("nested"))?;
+  std::fs::write(source_dir.path().join("nested").join("file.txt"), "content")?;
+  std::fs::write(source_dir.path().join("blocked.txt"), "content")?;
+  std::fs::create_dir_all(destination_dir.path().join("blocked.txt").join("child"))?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  cmd
+    .current_dir(working_dir.path())
+    .arg("-s")
+    .arg(source_dir.path())
+    .arg("-d")
+    .arg(destination_dir.path())
+    .arg("--skip-verify")
+    .arg("--report")
+    .arg("report.json")
+    .assert()
+    .code(1);
+
+  let report: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(working_dir.path().join("report.json"))?)?;
+
+  assert_eq!(report["totals"]["files"], 2);
+  assert_eq!(report["totals"]["succeeded"], 1);
+  assert_eq!(report["totals"]["failed"], 1);
+  assert_eq!(report["totals"]["bytes"], 14);
+  assert!(report["finished"].is_string(), "{report}");
+
+  let files = report["files"].as_array().ok_or("no files")?;
+  let copied = files.iter().find(|f| f["path"] == "nested/file.txt").ok_or("no copied file")?;
+  assert_eq!(copied["status"], "succeeded");
+  assert_eq!(copied["bytes_copied"], 7);
+
+  let blocked = files.iter().find(|f| f["path"] == "blocked.txt").ok_or("no blocked file")?;
+  assert_eq!(blocked["status"], "failed");
+  assert!(blocked["reason"].as_str().is_some_and(|r| r.starts_with("Could not move partial file into place")), "{blocked}");
+
+  Ok(())
+}
+
+#[test]
+fn writes_a_csv_report_with_checksums_to_stdout() -> Result<(), Box<dyn std::error::Error>> {
+  let working_dir = tempfile::tempdir()?;
+  let source_dir = tempfile::tempdir()?;
+  let destination_dir = tempfile::tempdir()?;
+
+  std::fs::write(source_dir.path().join("file.txt"), "content")?;
+
+  let mut cmd = Command::cargo_bin("coop").unwrap();
+
+  let output =
+    cmd
+      .current_dir(working_dir.path())
+      .arg("-s")
+      .arg(source_dir.path())
+      .arg("-d")
+      .arg(destination_dir.path())
+      .arg("--skip-verify")
+      .arg("--verify")
+      .arg("checksum")
+      .arg("--report")
+      .arg("-")
+      .arg("--report-format")
+      .arg("csv")
+      .assert()
+      .success()
+      .get_output()
+      .clone();
+
+  let stdout = String::from_utf8(output.stdout)?;
+  let rows: Vec<Vec<&str>> = stdout.lines().map(|l| l.split(',').collect()).collect();
+
+  assert_eq!(rows.len(), 3, "{stdout}");
+  assert_eq!(rows[0][0], "path");
+  assert_eq!(&rows[1][..3], &["file.txt", "7", "succeeded"]);
+  assert!(!rows[1][9].is_empty(), "{stdout}");
+  assert_eq!(rows[2][2], "total");
+  assert!(String::from_utf8(output.stderr)?.contains("Summary: 1 copied, 0 skipped, 0 failed"));
+
+  Ok(())
+}